@@ -1,10 +1,32 @@
-use std::env;
+use std::{env, path::PathBuf, process::Command};
 
 const CONFIG_FILE: &str = "asigntseonline.conf";
 
 fn main() -> std::io::Result<()> {
     let target = env::var("TARGET").expect("TARGET was not set");
 
+    let git_commit = git(&["rev-parse", "HEAD"]).unwrap_or_else(|| String::from("unknown"));
+
+    // rebuild when HEAD moves to another commit, either by checking out another ref or by committing to the current one
+    if let Some(git_dir) = git(&["rev-parse", "--git-dir"]).map(PathBuf::from) {
+        println!("cargo:rerun-if-changed={}", git_dir.join("HEAD").display());
+
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            let ref_file = git_dir.join(head_ref);
+            let packed_refs = git_dir.join("packed-refs");
+
+            for path in [ref_file, packed_refs].iter().filter(|path| path.exists()) {
+                println!("cargo:rerun-if-changed={}", path.display());
+            }
+        }
+    }
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/config/{}", CONFIG_FILE);
+
+    println!("cargo:rustc-env=BUILD_TARGET={}", target);
+    println!("cargo:rustc-env=GIT_COMMIT={}", git_commit);
+
     if cfg!(target_os = "windows") {
         let arch = if target.contains("x86_64-pc-windows-msvc") {
             "64bit "
@@ -28,3 +50,7 @@ fn main() -> std::io::Result<()> {
 
     Ok(())
 }
+
+fn git(args: &[&str]) -> Option<String> {
+    Command::new("git").args(args).output().ok().filter(|output| output.status.success()).map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -2,15 +2,19 @@
 #![allow(non_snake_case)]
 #![allow(unused_variables)]
 
+use std::{collections::HashMap, convert::TryFrom, fmt, ops::RangeInclusive, sync::Mutex};
+
 use log::error;
+use once_cell::sync::Lazy;
+use serde::Serialize;
 
-use super::return_codes::ReturnCode;
-use crate::{
-    client::{self, Client},
-    helpers::ffi,
-    idesscd::*,
-    logging,
+use super::{
+    return_codes::{Language, ReturnCode},
+    seapi::{AuthenticationResult, UnblockResult},
 };
+use crate::{backend, client, config, helpers::ffi, idesscd::*, lifecycle, logging};
+
+const ECHO_MESSAGE: &str = "fiskaltrust.middleware-wrapper-atrust";
 
 #[repr(u32)]
 #[allow(dead_code)]
@@ -31,19 +35,21 @@ pub extern "C" fn at_getLifecycleState(state: *mut LifecycleState) -> i32 {
 
 #[no_mangle]
 pub extern "C" fn at_getLifecycleStateWithTse(state: *mut LifecycleState, tseId: *const i8, tseIdLength: u32) -> i32 {
-    let tse_info = try_or_return!(|| Client::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
-
-    let lifecycle_state: LifecycleState = match tse_info.current_state {
-        TseStates::Uninitialized => LifecycleState::NotInitialized,
-        TseStates::Initialized => LifecycleState::Active,
-        TseStates::Terminated => LifecycleState::Disabled,
-    };
-
-    unsafe { ffi::set_u32_ptr(state as *mut u32, tse_info.current_state as u32) };
-    ReturnCode::ExecutionOk.into()
+    logging::call("at_getLifecycleStateWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        let tse_info = try_or_return!(|| backend::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        let lifecycle_state: LifecycleState = match tse_info.current_state {
+            TseStates::Uninitialized => LifecycleState::NotInitialized,
+            TseStates::Initialized => LifecycleState::Active,
+            TseStates::Terminated => LifecycleState::Disabled,
+        };
+
+        unsafe { ffi::set_u32_ptr(state as *mut u32, tse_info.current_state as u32) };
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -79,13 +85,15 @@ pub unsafe extern "C" fn at_getCertificate(cert: *mut *mut u8, certLength: *mut
 
 #[no_mangle]
 pub unsafe extern "C" fn at_getCertificateWithTse(cert: *mut *mut u8, certLength: *mut u32, tseId: *const i8, tseIdLength: u32) -> i32 {
-    let tse_info = try_or_return!(|| Client::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
-
-    ffi::set_cstr_array(cert, certLength, &tse_info.certificates_base64.into_iter().map(|c| String::from(c.as_str())).collect::<Vec<String>>());
-    ReturnCode::ExecutionOk.into()
+    logging::call("at_getCertificateWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        let tse_info = try_or_return!(|| backend::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        ffi::set_cstr_array(cert, certLength, &tse_info.certificates_base64.into_iter().map(|c| String::from(c.as_str())).collect::<Vec<String>>());
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -97,16 +105,18 @@ pub unsafe extern "C" fn at_getPublicKey(pubKey: *mut *mut u8, pubKeyLength: *mu
 
 #[no_mangle]
 pub unsafe extern "C" fn at_getPublicKeyWithTse(pubKey: *mut *mut u8, pubKeyLength: *mut u32, tseId: *const i8, tseIdLength: u32) -> i32 {
-    let tse_info = try_or_return!(|| Client::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
-
-    let public_key = ok_or_return!(tse_info.public_key_base64.decode(), |_| { ReturnCode::Unknown.into() });
-    ffi::set_byte_buf(pubKey, &public_key);
-    ffi::set_u32_ptr(pubKeyLength, public_key.len() as u32);
-
-    ReturnCode::ExecutionOk.into()
+    logging::call("at_getPublicKeyWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        let tse_info = try_or_return!(|| backend::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        let public_key = ok_or_return!(tse_info.public_key_base64.decode(), |_| { ReturnCode::Unknown.into() });
+        ffi::set_byte_buf(pubKey, &public_key);
+        ffi::set_u32_ptr(pubKeyLength, public_key.len() as u32);
+
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -118,15 +128,17 @@ pub unsafe extern "C" fn at_getOpenTransactions(transactionNumbers: *mut *mut u3
 
 #[no_mangle]
 pub unsafe extern "C" fn at_getOpenTransactionsWithTse(transactionNumbers: *mut *mut u32, transactionNumbersLength: *mut u32, tseId: *const i8, tseIdLength: u32) -> i32 {
-    let tse_info = try_or_return!(|| Client::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
+    logging::call("at_getOpenTransactionsWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        let tse_info = try_or_return!(|| backend::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
 
-    ffi::set_u32_buf(transactionNumbers, tse_info.current_started_transaction_numbers.iter().map(|t| t.to_owned() as u32).collect::<Vec<u32>>().as_slice());
-    ffi::set_u32_ptr(transactionNumbersLength, tse_info.current_number_of_started_transactions as u32);
+        ffi::set_u32_buf(transactionNumbers, tse_info.current_started_transaction_numbers.iter().map(|t| t.to_owned() as u32).collect::<Vec<u32>>().as_slice());
+        ffi::set_u32_ptr(transactionNumbersLength, tse_info.current_number_of_started_transactions as u32);
 
-    ReturnCode::ExecutionOk.into()
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 /// `not implemented`
@@ -156,13 +168,15 @@ pub unsafe extern "C" fn at_getSignatureCounter(counter: *mut u32) -> i32 {
 
 #[no_mangle]
 pub unsafe extern "C" fn at_getSignatureCounterWithTse(counter: *mut u32, tseId: *const i8, tseIdLength: u32) -> i32 {
-    let tse_info = try_or_return!(|| Client::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
-
-    ffi::set_u32_ptr(counter, tse_info.current_number_of_signatures as u32);
-    ReturnCode::ExecutionOk.into()
+    logging::call("at_getSignatureCounterWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        let tse_info = try_or_return!(|| backend::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        ffi::set_u32_ptr(counter, tse_info.current_number_of_signatures as u32);
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -174,13 +188,15 @@ pub unsafe extern "C" fn at_getSignatureAlgorithm(signatureAlgorithm: *mut *mut
 
 #[no_mangle]
 pub unsafe extern "C" fn at_getSignatureAlgorithmWithTse(signatureAlgorithm: *mut *mut i8, signatureAlgorithmLength: *mut u32, tseId: *const i8, tseIdLength: u32) -> i32 {
-    let tse_info = try_or_return!(|| Client::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
-
-    ffi::set_cstr(signatureAlgorithm as *mut *mut u8, signatureAlgorithmLength, tse_info.signature_algorithm);
-    ReturnCode::ExecutionOk.into()
+    logging::call("at_getSignatureAlgorithmWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        let tse_info = try_or_return!(|| backend::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        ffi::set_cstr(signatureAlgorithm as *mut *mut u8, signatureAlgorithmLength, tse_info.signature_algorithm);
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -192,42 +208,131 @@ pub unsafe extern "C" fn at_getLogTimeFormat(logTimeFormat: *mut *mut i8, logTim
 
 #[no_mangle]
 pub unsafe extern "C" fn at_getLogTimeFormatWithTse(logTimeFormat: *mut *mut i8, logTimeFormatLength: *mut u32, tseId: *const i8, tseIdLength: u32) -> i32 {
-    let tse_info = try_or_return!(|| Client::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
-
-    ffi::set_cstr(logTimeFormat as *mut *mut u8, logTimeFormatLength, tse_info.log_time_format);
-    ReturnCode::ExecutionOk.into()
+    logging::call("at_getLogTimeFormatWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        let tse_info = try_or_return!(|| backend::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        ffi::set_cstr(logTimeFormat as *mut *mut u8, logTimeFormatLength, tse_info.log_time_format);
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn at_getVersion(version: *mut *mut i8, versionLength: *mut u32) -> i32 {
-    log::info!("{}", "at_getVersion");
+    logging::call("at_getVersion", None, || {
+        ffi::set_cstr(version as *mut *mut u8, versionLength, env!("CARGO_PKG_VERSION").to_string());
 
-    ffi::set_cstr(version as *mut *mut u8, versionLength, env!("CARGO_PKG_VERSION").to_string());
+        ReturnCode::ExecutionOk.into()
+    })
+}
 
-    ReturnCode::ExecutionOk.into()
+#[no_mangle]
+pub unsafe extern "C" fn at_getErrorString(code: i32, errorString: *mut *mut i8, errorStringLength: *mut u32) -> i32 {
+    log::info!("{}", "at_getErrorString");
+
+    at_getErrorStringWithLanguage(code, Language::English as u32, errorString, errorStringLength)
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
-pub extern "C" fn at_getServiceVersion(version: *mut *mut i8, versionLength: *mut u32) -> i32 {
+pub unsafe extern "C" fn at_getErrorStringWithLanguage(code: i32, language: u32, errorString: *mut *mut i8, errorStringLength: *mut u32) -> i32 {
+    logging::call("at_getErrorStringWithLanguage", None, || {
+        let return_code = ok_or_return!(ReturnCode::try_from(code), |_| {
+            error!("unknown return code: {}", code);
+            ReturnCode::ParameterMismatch.into()
+        });
+
+        let language = ok_or_return!(Language::try_from(language), |_| {
+            error!("unknown language: {}", language);
+            ReturnCode::ParameterMismatch.into()
+        });
+
+        ffi::set_cstr(errorString as *mut *mut u8, errorStringLength, format!("{}: {}", return_code, return_code.description(language)));
+
+        ReturnCode::ExecutionOk.into()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn at_getServiceVersion(version: *mut *mut i8, versionLength: *mut u32) -> i32 {
     log::info!("{}", "at_getServiceVersion");
 
-    ReturnCode::NotImplemented.into()
+    at_getServiceVersionWithTse(version, versionLength, b"default".as_ptr() as *const i8, "default".len() as u32)
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
-pub extern "C" fn at_getVersionDetails(versionDetails: *mut *mut i8, versionDetailsLength: *mut u32) -> i32 {
-    log::info!("{}", "at_getVersionDetails");
+pub unsafe extern "C" fn at_getServiceVersionWithTse(version: *mut *mut i8, versionLength: *mut u32, tseId: *const i8, tseIdLength: u32) -> i32 {
+    logging::call("at_getServiceVersionWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        let scu_version = try_or_return!(|| backend::get(ffi::from_cstr(tseId, tseIdLength))?.get_version(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        ffi::set_cstr(version as *mut *mut u8, versionLength, scu_version.version);
+        ReturnCode::ExecutionOk.into()
+    })
+}
 
-    ReturnCode::NotImplemented.into()
+#[derive(Serialize)]
+struct VersionDetails {
+    version: &'static str,
+    git_commit: &'static str,
+    target: &'static str,
+    features: Vec<&'static str>,
+    tses: Vec<TseVersionDetails>,
+}
+
+#[derive(Serialize)]
+struct TseVersionDetails {
+    name: String,
+    scu_url: String,
+    scu_version: Option<String>,
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn at_getVersionDetails(versionDetails: *mut *mut i8, versionDetailsLength: *mut u32) -> i32 {
+    logging::call("at_getVersionDetails", None, || {
+        let mut features = vec![];
+        if cfg!(feature = "not_implemented") {
+            features.push("not_implemented");
+        }
+        if cfg!(feature = "mocks") {
+            features.push("mocks");
+        }
+
+        let tses = config::get_all_tss()
+            .into_iter()
+            .map(|config| {
+                let scu_version = backend::get(config.name.clone()).and_then(|client| client.get_version()).map(|v| v.version);
+                if let Err(err) = &scu_version {
+                    log::warn!("could not get scu version of {}: {}", config.name, err);
+                }
+
+                TseVersionDetails {
+                    name: config.name,
+                    scu_url: config.scu_url,
+                    scu_version: scu_version.ok(),
+                }
+            })
+            .collect();
+
+        let version_details = VersionDetails {
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: env!("GIT_COMMIT"),
+            target: env!("BUILD_TARGET"),
+            features,
+            tses,
+        };
+
+        let version_details = ok_or_return!(serde_json::to_string(&version_details), |err| {
+            error!("{}", err);
+            ReturnCode::Unknown.into()
+        });
+
+        ffi::set_cstr(versionDetails as *mut *mut u8, versionDetailsLength, version_details);
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -239,16 +344,18 @@ pub unsafe extern "C" fn at_getSerialNumber(serial: *mut *mut u8, serialLength:
 
 #[no_mangle]
 pub unsafe extern "C" fn at_getSerialNumberWithTse(serial: *mut *mut u8, serialLength: *mut u32, tseId: *const i8, tseIdLength: u32) -> i32 {
-    let tse_info = try_or_return!(|| Client::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
-
-    let serial_number = ok_or_return!(hex::decode(tse_info.serial_number_octet), |_| { ReturnCode::Unknown.into() });
-
-    ffi::set_byte_buf(serial, &serial_number);
-    ffi::set_u32_ptr(serialLength, serial_number.len() as u32);
-    ReturnCode::ExecutionOk.into()
+    logging::call("at_getSerialNumberWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        let tse_info = try_or_return!(|| backend::get(ffi::from_cstr(tseId, tseIdLength))?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        let serial_number = ok_or_return!(hex::decode(tse_info.serial_number_octet), |_| { ReturnCode::Unknown.into() });
+
+        ffi::set_byte_buf(serial, &serial_number);
+        ffi::set_u32_ptr(serialLength, serial_number.len() as u32);
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -260,47 +367,152 @@ pub extern "C" fn at_preload() -> i32 {
 
 #[no_mangle]
 pub extern "C" fn at_load() -> i32 {
-    log::info!("{}", "at_load");
-
-    if !crate::config::has_read_config() {
-        return ReturnCode::ConfigFileNotFound.into();
-    }
+    logging::call("at_load", None, || {
+        if !crate::config::has_read_config() {
+            return ReturnCode::ConfigFileNotFound.into();
+        }
 
-    match logging::configure_logging() {
-        Ok(_) => {}
-        Err(logging::Error::LoggerAlreadyConfigured) => {}
-        Err(err) => {
-            println!("{}", err);
-            return ReturnCode::Unknown.into();
+        match logging::configure_logging() {
+            Ok(_) => {}
+            Err(logging::Error::LoggerAlreadyConfigured) => {}
+            Err(err) => {
+                println!("{}", err);
+                return ReturnCode::Unknown.into();
+            }
         }
-    }
 
-    ReturnCode::ExecutionOk.into()
+        try_or_return!(lifecycle::load, |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn at_unload() -> i32 {
-    log::info!("{}", "at_unload");
+    logging::call("at_unload", None, || {
+        lifecycle::unload();
 
-    ReturnCode::ExecutionOk.into()
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
 pub extern "C" fn at_verifyConfigEntry() -> i32 {
     log::info!("{}", "at_verifyConfigEntry");
 
     at_verifyConfigEntryWithTse(b"default".as_ptr() as *const i8, "default".len() as u32)
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
 pub extern "C" fn at_verifyConfigEntryWithTse(configEntry: *const i8, configEntryLength: u32) -> i32 {
-    ReturnCode::NotImplemented.into()
+    logging::call("at_verifyConfigEntryWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let tse_id = ffi::from_cstr(configEntry, configEntryLength);
+
+        log::info!("verifying config entry {}", tse_id);
+
+        let mut passed = vec![];
+        let result = verify_config_entry(&tse_id, &mut passed);
+
+        for (check, details) in passed.iter() {
+            log::info!("[ok] {}: {}", check, details);
+        }
+
+        match result {
+            Ok(()) => {
+                log::info!("config entry {} verified", tse_id);
+                ReturnCode::ExecutionOk.into()
+            }
+            Err((check, return_code, reason)) => {
+                error!("[failed] {}: {} ({})", check, reason, return_code);
+                for check in VERIFY_CONFIG_ENTRY_CHECKS.iter().skip(passed.len() + 1) {
+                    log::warn!("[skipped] {}", check);
+                }
+                return_code.into()
+            }
+        }
+    })
+}
+
+const VERIFY_CONFIG_ENTRY_CHECKS: [&str; 7] = ["config entry", "scu_url", "scu reachable", "tse info", "tse state", "serial number", "certificates"];
+
+fn verify_config_entry(tse_id: &str, passed: &mut Vec<(&'static str, String)>) -> Result<(), (&'static str, ReturnCode, String)> {
+    macro_rules! check {
+        ($result:expr) => {{
+            let check = VERIFY_CONFIG_ENTRY_CHECKS[passed.len()];
+            match $result {
+                Ok((value, details)) => {
+                    passed.push((check, details));
+                    value
+                }
+                Err((return_code, reason)) => return Err((check, return_code, reason)),
+            }
+        }};
+    }
+
+    let problems: Vec<String> = config::validate().iter().filter(|problem| problem.section == tse_id).map(ToString::to_string).collect();
+
+    let config = check!(match config::get_tss(tse_id) {
+        _ if !problems.is_empty() => Err((ReturnCode::InvalidConfig, problems.join(", "))),
+        Some(config) => Ok((config, format!("found in {}", config::CONFIG_FILE.load()))),
+        None => Err((ReturnCode::ConfigValueNotFound, format!("no entry named {} in {}", tse_id, config::CONFIG_FILE.load()))),
+    });
+
+    check!(config
+        .scu_urls()
+        .iter()
+        .map(|scu_url| match reqwest::Url::parse(scu_url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(()),
+            Ok(url) => Err((ReturnCode::InvalidConfig, format!("unsupported scheme {} in {}", url.scheme(), scu_url))),
+            Err(err) => Err((ReturnCode::InvalidConfig, format!("{} is not a valid url: {}", scu_url, err))),
+        })
+        .collect::<Result<Vec<()>, _>>()
+        .map(|_| ((), config.scu_urls().join(", "))));
+
+    let client = ok_or_return!(backend::get(tse_id.to_string()), |err: backend::Error| {
+        let reason = err.to_string();
+        Err((VERIFY_CONFIG_ENTRY_CHECKS[passed.len()], ReturnCode::from(err), reason))
+    });
+
+    let echo_request = ScuDeEchoRequest { message: String::from(ECHO_MESSAGE) };
+    check!(match client.echo(&echo_request) {
+        Ok(response) if response.message == echo_request.message => Ok(((), format!("{} answered echo", config.scu_urls().join(" or ")))),
+        Ok(response) => Err((ReturnCode::TseResponseDataInvalid, format!("echo returned {:?} instead of {:?}", response.message, echo_request.message))),
+        Err(err) => Err((Into::<ReturnCode>::into(err), format!("{} did not answer echo", config.scu_urls().join(" or ")))),
+    });
+
+    let tse_info = check!(client
+        .get_tse_info()
+        .map(|tse_info| {
+            let details = format!("firmware {}, certification {}", tse_info.firmware_identification, tse_info.certification_identification);
+            (tse_info, details)
+        })
+        .map_err(|err| {
+            let reason = err.to_string();
+            (Into::<ReturnCode>::into(err), reason)
+        }));
+
+    check!(match tse_info.current_state {
+        TseStates::Initialized => Ok(((), format!("{:?}", tse_info.current_state))),
+        TseStates::Uninitialized => Err((ReturnCode::SeApiNotInitialized, String::from("tse is not initialized"))),
+        TseStates::Terminated => Err((ReturnCode::SecureElementDisabled, String::from("tse is terminated"))),
+    });
+
+    check!(hex::decode(&tse_info.serial_number_octet)
+        .map(|_| ((), tse_info.serial_number_octet.clone()))
+        .map_err(|err| (ReturnCode::TseResponseDataInvalid, format!("serial number {} is not valid hex: {}", tse_info.serial_number_octet, err))));
+
+    check!(if tse_info.certificates_base64.is_empty() {
+        Err((ReturnCode::ExportCertificateFailed, String::from("tse has no certificates")))
+    } else if let Some(err) = tse_info.certificates_base64.iter().find_map(|c| c.decode().err()) {
+        Err((ReturnCode::TseResponseDataInvalid, format!("certificate is not valid base64: {}", err)))
+    } else {
+        Ok(((), format!("{} certificate(s)", tse_info.certificates_base64.len())))
+    });
+
+    Ok(())
 }
 
 #[no_mangle]
@@ -327,18 +539,20 @@ pub extern "C" fn at_registerClientId(clientId: *const i8, clientIdLength: u32)
 
 #[no_mangle]
 pub extern "C" fn at_registerClientIdWithTse(clientId: *const i8, clientIdLength: u32, configEntry: *const i8, configEntryLength: u32) -> i32 {
-    let register_client_id_request = RegisterClientIdRequest { client_id: ffi::from_cstr(clientId, clientIdLength) };
-
-    let register_client_id_response = try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength))?.register_client_id(&register_client_id_request), |err: client::Error| {
-        error!("{}", err);
-        match err {
-            client::Error::Unsuccessful(_) => ReturnCode::ClientIdNotRegistered,
-            err => Into::<ReturnCode>::into(err),
-        }
-        .into()
-    });
-
-    ReturnCode::ExecutionOk.into()
+    logging::call("at_registerClientIdWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let register_client_id_request = RegisterClientIdRequest { client_id: ffi::from_cstr(clientId, clientIdLength) };
+
+        let register_client_id_response = try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength))?.register_client_id(&register_client_id_request), |err: backend::Error| {
+            error!("{}", err);
+            match err {
+                backend::Error::Unsuccessful(_) => ReturnCode::ClientIdNotRegistered,
+                err => Into::<ReturnCode>::into(err),
+            }
+            .into()
+        });
+
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -362,81 +576,329 @@ pub unsafe extern "C" fn at_getRegisteredClients(clients: *mut *mut u8, clientsL
 
 #[no_mangle]
 pub unsafe extern "C" fn at_getRegisteredClientsWithTse(clients: *mut *mut u8, clientsLength: *mut u32, configEntry: *const i8, configEntryLength: u32) -> i32 {
-    let tse_info = try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength))?.get_tse_info(), |err: client::Error| {
-        error!("{}", err);
-        match err {
-            client::Error::Unsuccessful(_) => ReturnCode::CannotRetrieveRegisteredClientIds,
-            err => Into::<ReturnCode>::into(err),
-        }
-        .into()
-    });
-
-    ffi::set_cstr_array(clients, clientsLength, &tse_info.current_client_ids);
-    ReturnCode::ExecutionOk.into()
+    logging::call("at_getRegisteredClientsWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let tse_info = try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength))?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            match err {
+                backend::Error::Unsuccessful(_) => ReturnCode::CannotRetrieveRegisteredClientIds,
+                err => Into::<ReturnCode>::into(err),
+            }
+            .into()
+        });
+
+        ffi::set_cstr_array(clients, clientsLength, &tse_info.current_client_ids);
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
 pub extern "C" fn at_setPace(paceUser: *const i8, paceUserLength: u32, pacePin: *const i8, pacePinLength: u32, paceApiKey: *const i8, paceApiKeyLength: u32) -> i32 {
     log::info!("{}", "at_setPace");
 
     at_setPaceWithTse(paceUser, paceUserLength, pacePin, pacePinLength, paceApiKey, paceApiKeyLength, b"default".as_ptr() as *const i8, "default".len() as u32)
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
 pub extern "C" fn at_setPaceWithTse(paceUser: *const i8, paceUserLength: u32, pacePin: *const i8, pacePinLength: u32, paceApiKey: *const i8, paceApiKeyLength: u32, tseId: *const i8, tseIdLength: u32) -> i32 {
-    ReturnCode::NotImplemented.into()
+    logging::call("at_setPaceWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        if !lifecycle::is_loaded() {
+            error!("{}", backend::Error::NotLoaded);
+            return ReturnCode::AtLoadNotCalled.into();
+        }
+
+        let tse_id = ffi::from_cstr(tseId, tseIdLength);
+
+        if config::get_tss(&tse_id).is_none() {
+            error!("config entry {} not found", tse_id);
+            return ReturnCode::ConfigValueNotFound.into();
+        }
+
+        for (name, value) in [
+            ("paceUser", ffi::try_from_cstr(paceUser, paceUserLength)),
+            ("pacePin", ffi::try_from_cstr(pacePin, pacePinLength)),
+            ("paceApiKey", ffi::try_from_cstr(paceApiKey, paceApiKeyLength)),
+        ] {
+            if value.unwrap_or_default().is_empty() {
+                error!("{} must not be empty", name);
+                return ReturnCode::MissingParameter.into();
+            }
+        }
+
+        log::info!("PACE credentials for {} are ignored, cloud TSEs are provisioned server-side", tse_id);
+
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
 pub extern "C" fn at_addUserEntropy(entropyString: *const i8, entropyStringLength: u32) -> i32 {
     log::info!("{}", "at_addUserEntropy");
 
     at_addUserEntropyWithTse(entropyString, entropyStringLength, b"default".as_ptr() as *const i8, "default".len() as u32)
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
 pub extern "C" fn at_addUserEntropyWithTse(entropyString: *const i8, entropyStringLength: u32, tseId: *const i8, tseIdLength: u32) -> i32 {
-    ReturnCode::NotImplemented.into()
+    logging::call("at_addUserEntropyWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        if !lifecycle::is_loaded() {
+            error!("{}", backend::Error::NotLoaded);
+            return ReturnCode::AtLoadNotCalled.into();
+        }
+
+        let tse_id = ffi::from_cstr(tseId, tseIdLength);
+
+        if config::get_tss(&tse_id).is_none() {
+            error!("config entry {} not found", tse_id);
+            return ReturnCode::ConfigValueNotFound.into();
+        }
+
+        if entropyString.is_null() || entropyStringLength == 0 {
+            error!("entropyString must not be empty");
+            return ReturnCode::MissingParameter.into();
+        }
+
+        log::info!("user entropy for {} is ignored, cloud TSEs are provisioned server-side", tse_id);
+
+        ReturnCode::ExecutionOk.into()
+    })
+}
+
+pub(crate) const ADMIN_PIN_LENGTH: RangeInclusive<usize> = 5..=8;
+const ADMIN_PUK_LENGTH: RangeInclusive<usize> = 6..=10;
+
+/// Failed authentications after which the admin pin is blocked until it is unblocked with the puk.
+const ADMIN_PIN_RETRIES: i16 = 3;
+
+/// An admin pin or puk, kept and compared as the bytes it was given as since it does not have to be valid UTF-8. Its `Debug` implementation prints `***`.
+#[derive(Clone, PartialEq, Eq)]
+struct AdminSecret(Vec<u8>);
+
+impl From<&[u8]> for AdminSecret {
+    fn from(bytes: &[u8]) -> AdminSecret {
+        AdminSecret(bytes.to_vec())
+    }
+}
+
+impl fmt::Debug for AdminSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+struct AdminCredentials {
+    pin: AdminSecret,
+    puk: AdminSecret,
+    remaining_retries: i16,
+}
+
+static ADMIN_CREDENTIALS: Lazy<Mutex<HashMap<String, AdminCredentials>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Checks the given pin against the admin pin set with `at_setPins`, blocking it after `ADMIN_PIN_RETRIES` failed attempts.
+/// Returns the result and the remaining retries or `None` if no pin was set for the TSE.
+pub(crate) fn authenticate_admin(tse_id: &str, pin: &[u8]) -> Option<(AuthenticationResult, i16)> {
+    let mut admin_credentials = ok_or_return!(ADMIN_CREDENTIALS.lock(), |_| None);
+    let credentials = admin_credentials.get_mut(tse_id)?;
+
+    let result = if credentials.remaining_retries == 0 {
+        AuthenticationResult::PinIsBlocked
+    } else if credentials.pin == AdminSecret::from(pin) {
+        credentials.remaining_retries = ADMIN_PIN_RETRIES;
+        AuthenticationResult::Ok
+    } else {
+        credentials.remaining_retries -= 1;
+        AuthenticationResult::Failed
+    };
+
+    Some((result, credentials.remaining_retries))
+}
+
+/// Replaces the admin pin if the puk matches the one set with `at_setPins`. Returns `None` if no pin was set for the TSE.
+pub(crate) fn unblock_admin(tse_id: &str, puk: &[u8], new_pin: &[u8]) -> Option<UnblockResult> {
+    let mut admin_credentials = ok_or_return!(ADMIN_CREDENTIALS.lock(), |_| None);
+    let credentials = admin_credentials.get_mut(tse_id)?;
+
+    if credentials.puk != AdminSecret::from(puk) {
+        return Some(UnblockResult::Failed);
+    }
+
+    credentials.pin = AdminSecret::from(new_pin);
+    credentials.remaining_retries = ADMIN_PIN_RETRIES;
+
+    Some(UnblockResult::Ok)
+}
+
+/// Forgets the admin pins and puks of all TSEs, they have to be set again with `at_setPins` after the next `at_load`.
+pub(crate) fn reset_admin_credentials() {
+    if let Ok(mut admin_credentials) = ADMIN_CREDENTIALS.lock() {
+        admin_credentials.clear();
+    }
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
-pub extern "C" fn at_setPins(adminPin: *const u8, adminPinLength: u32, adminPuk: *const u8, adminPukLength: u32) -> i32 {
+pub unsafe extern "C" fn at_setPins(adminPin: *const u8, adminPinLength: u32, adminPuk: *const u8, adminPukLength: u32) -> i32 {
     log::info!("{}", "at_setPins");
 
     at_setPinsWithTse(adminPin, adminPinLength, adminPuk, adminPukLength, b"default".as_ptr() as *const i8, "default".len() as u32)
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
-pub extern "C" fn at_setPinsWithTse(adminPin: *const u8, adminPinLength: u32, adminPuk: *const u8, adminPukLength: u32, tseId: *const i8, tseIdLength: u32) -> i32 {
-    ReturnCode::NotImplemented.into()
+pub unsafe extern "C" fn at_setPinsWithTse(adminPin: *const u8, adminPinLength: u32, adminPuk: *const u8, adminPukLength: u32, tseId: *const i8, tseIdLength: u32) -> i32 {
+    logging::call("at_setPinsWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        if !lifecycle::is_loaded() {
+            error!("{}", backend::Error::NotLoaded);
+            return ReturnCode::AtLoadNotCalled.into();
+        }
+
+        let tse_id = ffi::from_cstr(tseId, tseIdLength);
+
+        if config::get_tss(&tse_id).is_none() {
+            error!("config entry {} not found", tse_id);
+            return ReturnCode::ConfigValueNotFound.into();
+        }
+
+        if adminPin.is_null() || adminPuk.is_null() {
+            error!("adminPin and adminPuk must be set");
+            return ReturnCode::MissingParameter.into();
+        }
+
+        if !ADMIN_PIN_LENGTH.contains(&(adminPinLength as usize)) || !ADMIN_PUK_LENGTH.contains(&(adminPukLength as usize)) {
+            error!("adminPin must be {:?} and adminPuk {:?} bytes long", ADMIN_PIN_LENGTH, ADMIN_PUK_LENGTH);
+            return ReturnCode::AtSetPinsFailed.into();
+        }
+
+        let credentials = AdminCredentials {
+            pin: AdminSecret::from(ffi::from_cba(adminPin, adminPinLength)),
+            puk: AdminSecret::from(ffi::from_cba(adminPuk, adminPukLength)),
+            remaining_retries: ADMIN_PIN_RETRIES,
+        };
+
+        let mut admin_credentials = ok_or_return!(ADMIN_CREDENTIALS.lock(), |_| ReturnCode::Unknown.into());
+
+        match admin_credentials.get(&tse_id) {
+            Some(existing) if existing.pin == credentials.pin && existing.puk == credentials.puk => ReturnCode::ExecutionOk.into(),
+            Some(_) => {
+                error!("pins for {} are already set", tse_id);
+                ReturnCode::SeAlreadyProvisioned.into()
+            }
+            None => {
+                log::info!("admin pin for {} is only used for local authentication, cloud TSEs are provisioned server-side", tse_id);
+                admin_credentials.insert(tse_id, credentials);
+                ReturnCode::ExecutionOk.into()
+            }
+        }
+    })
 }
 
-/// `not implemented`
+/// The implemented functions in the order used by `at_checkCompatibility` together with the SCU endpoints they need.
+const COMPATIBILITY: [(&str, &[&str]); 39] = [
+    ("initializeDescriptionSet", &["tsestate"]),
+    ("updateTime", &[]),
+    ("updateTimeWithTimeSync", &["executesettsetime"]),
+    ("disableSecureElement", &["tsestate"]),
+    ("startTransaction", &["starttransaction"]),
+    ("updateTransaction", &["updatetransaction"]),
+    ("finishTransaction", &["finishtransaction"]),
+    ("exportData", &["startexportsession", "exportdata", "endexportsession"]),
+    ("exportDataWithClientId", &["startexportsession", "exportdata", "endexportsession"]),
+    ("exportCertificates", &["tseinfo"]),
+    ("exportSerialNumbers", &["tseinfo"]),
+    ("getMaxNumberOfClients", &["tseinfo"]),
+    ("getCurrentNumberOfClients", &["tseinfo"]),
+    ("getMaxNumberOfTransactions", &["tseinfo"]),
+    ("getCurrentNumberOfTransactions", &["tseinfo"]),
+    ("getSupportedTransactionUpdateVariants", &[]),
+    ("authenticateUser", &[]),
+    ("logOut", &[]),
+    ("unblockUser", &[]),
+    ("at_getLifecycleState", &["tseinfo"]),
+    ("at_suspendSecureElement", &[]),
+    ("at_unsuspendSecureElement", &[]),
+    ("at_getCertificate", &["tseinfo"]),
+    ("at_getPublicKey", &["tseinfo"]),
+    ("at_getOpenTransactions", &["tseinfo"]),
+    ("at_getSignatureCounter", &["tseinfo"]),
+    ("at_getSignatureAlgorithm", &["tseinfo"]),
+    ("at_getLogTimeFormat", &["tseinfo"]),
+    ("at_getVersion", &[]),
+    ("at_getServiceVersion", &["version"]),
+    ("at_getSerialNumber", &["tseinfo"]),
+    ("at_registerClientId", &["registerclientid"]),
+    ("at_getMaxLicencedClients", &["tseinfo"]),
+    ("at_getRegisteredClients", &["tseinfo"]),
+    ("at_setPins", &[]),
+    ("at_runSelfTests", &["executeselftest"]),
+    ("at_checkSecureState", &["tseinfo"]),
+    ("at_reloadSecureElement", &["echo"]),
+    ("at_verifyConfigEntry", &["echo", "tseinfo"]),
+];
+
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
 pub extern "C" fn at_checkCompatibility(startIndex: u32, indexCnt: u32) -> i32 {
     log::info!("{}", "at_checkCompatibility");
 
-    ReturnCode::NotImplemented.into()
+    at_checkCompatibilityWithTse(startIndex, indexCnt, b"default".as_ptr() as *const i8, "default".len() as u32)
+}
+
+#[no_mangle]
+pub extern "C" fn at_checkCompatibilityWithTse(startIndex: u32, indexCnt: u32, tseId: *const i8, tseIdLength: u32) -> i32 {
+    logging::call("at_checkCompatibilityWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        if !lifecycle::is_loaded() {
+            error!("{}", backend::Error::NotLoaded);
+            return ReturnCode::AtLoadNotCalled.into();
+        }
+
+        let functions = some_or_return!(COMPATIBILITY.get(startIndex as usize..(startIndex as usize).saturating_add(indexCnt as usize).min(COMPATIBILITY.len())), {
+            error!("startIndex {} is out of range, {} functions are known", startIndex, COMPATIBILITY.len());
+            ReturnCode::ParameterMismatch.into()
+        });
+
+        let compatibility = try_or_return!(|| client::negotiate(ffi::from_cstr(tseId, tseIdLength)), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        log::info!("scu supports url version {}", compatibility.url_version);
+
+        let mut return_code = ReturnCode::ExecutionOk;
+        for (function, endpoints) in functions {
+            match endpoints.iter().find(|endpoint| !compatibility.endpoints.contains(endpoint)) {
+                Some(endpoint) => {
+                    log::warn!("{} is not supported, the scu is missing the {} endpoint", function, endpoint);
+                    return_code = ReturnCode::FunctionNotSupported;
+                }
+                None => log::info!("{} is supported", function),
+            }
+        }
+
+        return_code.into()
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn at_getCircuitBreakerState(state: *mut client::CircuitBreakerState) -> i32 {
+    log::info!("{}", "at_getCircuitBreakerState");
+
+    at_getCircuitBreakerStateWithTse(state, b"default".as_ptr() as *const i8, "default".len() as u32)
+}
+
+#[no_mangle]
+pub extern "C" fn at_getCircuitBreakerStateWithTse(state: *mut client::CircuitBreakerState, tseId: *const i8, tseIdLength: u32) -> i32 {
+    logging::call("at_getCircuitBreakerStateWithTse", Some(&ffi::from_cstr(tseId, tseIdLength)), || {
+        if !lifecycle::is_loaded() {
+            error!("{}", backend::Error::NotLoaded);
+            return ReturnCode::AtLoadNotCalled.into();
+        }
+
+        let tse_id = ffi::from_cstr(tseId, tseIdLength);
+
+        if config::get_tss(&tse_id).is_none() {
+            error!("config entry {} not found", tse_id);
+            return ReturnCode::ConfigValueNotFound.into();
+        }
+
+        unsafe { ffi::set_u32_ptr(state as *mut u32, client::circuit_breaker_state(&tse_id) as u32) };
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -448,48 +910,105 @@ pub extern "C" fn at_runSelfTests() -> i32 {
 
 #[no_mangle]
 pub extern "C" fn at_runSelfTestsWithTse(configEntry: *const i8, configEntryLength: u32) -> i32 {
-    try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength))?.execute_self_test(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
-
-    ReturnCode::ExecutionOk.into()
+    logging::call("at_runSelfTestsWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength))?.execute_self_test(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
 pub extern "C" fn at_checkSecureState() -> i32 {
     log::info!("{}", "at_checkSecureState");
 
     at_checkSecureStateWithTse(b"default".as_ptr() as *const i8, "default".len() as u32)
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
 pub extern "C" fn at_checkSecureStateWithTse(configEntry: *const i8, configEntryLength: u32) -> i32 {
-    ReturnCode::NotImplemented.into()
+    logging::call("at_checkSecureStateWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let tse_id = ffi::from_cstr(configEntry, configEntryLength);
+
+        let tse_info = try_or_return!(|| backend::get(tse_id.clone())?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        // the scu has no suspended state and at_suspendSecureElement does not change the tse, so a tse is never reported as suspended
+        match tse_info.current_state {
+            TseStates::Uninitialized => ReturnCode::SeApiNotInitialized,
+            TseStates::Terminated => ReturnCode::SecureElementDisabled,
+            TseStates::Initialized if backend::is_time_not_set(&tse_id) => ReturnCode::TimeNotSet,
+            TseStates::Initialized if has_expired_certificate(&tse_info.certificates_base64) => ReturnCode::CertificateExpired,
+            TseStates::Initialized => ReturnCode::SeInSecureState,
+        }
+        .into()
+    })
+}
+
+fn has_expired_certificate(certificates: &[Base64]) -> bool {
+    let now = chrono::Utc::now().timestamp();
+
+    certificates.iter().any(|certificate| {
+        let certificate = ok_or_return!(certificate.decode(), |err| {
+            log::warn!("could not decode certificate: {}", err);
+            false
+        });
+
+        let not_after = if certificate.starts_with(b"-----BEGIN") {
+            x509_parser::pem::parse_x509_pem(&certificate).ok().and_then(|(_, pem)| pem.parse_x509().ok().map(|c| c.validity().not_after.timestamp()))
+        } else {
+            x509_parser::parse_x509_certificate(&certificate).ok().map(|(_, c)| c.validity().not_after.timestamp())
+        };
+
+        match not_after {
+            Some(not_after) => not_after < now,
+            None => {
+                log::warn!("could not parse certificate");
+                false
+            }
+        }
+    })
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
 pub extern "C" fn at_reloadSecureElement() -> i32 {
     log::info!("{}", "at_reloadSecureElement");
 
     at_reloadSecureElementWithTse(b"default".as_ptr() as *const i8, "default".len() as u32)
 }
 
-/// `not implemented`
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
 pub extern "C" fn at_reloadSecureElementWithTse(configEntry: *const i8, configEntryLength: u32) -> i32 {
-    ReturnCode::NotImplemented.into()
+    logging::call("at_reloadSecureElementWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let tse_id = ffi::from_cstr(configEntry, configEntryLength);
+
+        match config::reload_tss(&tse_id) {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                error!("config entry {} not found after reload", tse_id);
+                return ReturnCode::ConfigValueNotFound.into();
+            }
+            Err(_) => {
+                error!("{} is invalid, keeping the previous config entry {}", config::CONFIG_FILE.load(), tse_id);
+                return ReturnCode::InvalidConfig.into();
+            }
+        }
+
+        let echo_request = ScuDeEchoRequest { message: String::from(ECHO_MESSAGE) };
+
+        backend::invalidate(&tse_id);
+
+        try_or_return!(|| backend::get(tse_id.clone())?.echo(&echo_request), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
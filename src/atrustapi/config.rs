@@ -2,26 +2,31 @@
 #![allow(non_snake_case)]
 #![allow(unused_variables)]
 
-use crate::{atrustapi::return_codes::ReturnCode, config, helpers::ffi, logging};
+use crate::{atrustapi::return_codes::ReturnCode, backend, config, heartbeat, helpers::ffi, logging, reload};
 
 #[no_mangle]
 pub extern "C" fn cfgSetConfigFile(path: *const i8, pathLength: u32) -> i32 {
-    log::info!("{}", "cfgSetConfigFile");
+    logging::call("cfgSetConfigFile", None, || {
+        if !crate::config::set_config_file(&ffi::from_cstr(path, pathLength)) {
+            return ReturnCode::InvalidConfig.into();
+        }
 
-    if !crate::config::set_config_file(&ffi::from_cstr(path, pathLength)) {
-        return ReturnCode::ConfigFileNotFound.into();
-    }
+        backend::clear();
 
-    match logging::configure_logging() {
-        Ok(_) => {}
-        Err(logging::Error::LoggerAlreadyConfigured) => {}
-        Err(err) => {
-            println!("{}", err);
-            return ReturnCode::Unknown.into();
+        match logging::configure_logging() {
+            Ok(_) => {}
+            Err(logging::Error::LoggerAlreadyConfigured) => {}
+            Err(err) => {
+                println!("{}", err);
+                return ReturnCode::Unknown.into();
+            }
         }
-    }
 
-    ReturnCode::ExecutionOk.into()
+        reload::restart();
+        config::log_effective();
+
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -171,28 +176,94 @@ pub extern "C" fn cfgSetLogStderrColors(enabled: bool) -> i32 {
     ReturnCode::NotImplemented.into()
 }
 
+/// Writes the log file as `text` or as one JSON object per line with `json`.
 #[no_mangle]
-pub extern "C" fn cfgSetHttpProxy(proxyUrl: *const i8, proxyUrlLength: u32) -> i32 {
-    log::info!("{}", "cfgSetHttpProxy");
+pub extern "C" fn cfgSetLogFormat(format: *const i8, formatLength: u32) -> i32 {
+    logging::call("cfgSetLogFormat", None, || {
+        let format = ffi::try_from_cstr(format, formatLength).unwrap_or_default();
 
-    let mut general_config = ok_or_return!(config::GENERAL_CONFIG.lock(), |err| ReturnCode::Unknown.into());
+        set_logging_config(|general_config| general_config.log_format = Some(format).filter(|format| !format.is_empty()))
+    })
+}
 
-    general_config.http_proxy = Some(ffi::from_cstr(proxyUrl, proxyUrlLength));
+/// Rotates the log file once it is larger than `size` bytes, `0` disables the size based rotation.
+#[no_mangle]
+pub extern "C" fn cfgSetLogRotateSize(size: u64) -> i32 {
+    logging::call("cfgSetLogRotateSize", None, || set_logging_config(|general_config| general_config.log_rotate_size = Some(size).filter(|size| *size > 0)))
+}
 
-    ReturnCode::ExecutionOk.into()
+/// Rotates the log file every `day`, `hour`, `minute` or `second`, an empty value disables the age based rotation.
+#[no_mangle]
+pub extern "C" fn cfgSetLogRotateAge(age: *const i8, ageLength: u32) -> i32 {
+    logging::call("cfgSetLogRotateAge", None, || {
+        let age = ffi::try_from_cstr(age, ageLength).unwrap_or_default();
+
+        set_logging_config(|general_config| general_config.log_rotate_age = Some(age).filter(|age| !age.is_empty()))
+    })
 }
 
+/// Keeps only the newest `count` rotated log files, `0` keeps all of them.
 #[no_mangle]
-pub extern "C" fn cfgSetHttpProxyWithUsernameAndPassword(proxyUrl: *const i8, proxyUrlLength: u32, proxyUsername: *const i8, proxyUsernameLength: u32, proxyPassword: *const i8, proxyPasswordLength: u32) -> i32 {
-    log::info!("{}", "cfgSetHttpProxyWithUsernameAndPassword");
+pub extern "C" fn cfgSetLogKeepFiles(count: u32) -> i32 {
+    logging::call("cfgSetLogKeepFiles", None, || set_logging_config(|general_config| general_config.log_keep_files = Some(count as usize).filter(|count| *count > 0)))
+}
 
-    let mut general_config = ok_or_return!(config::GENERAL_CONFIG.lock(), |err| ReturnCode::Unknown.into());
+/// Compresses rotated log files with gzip.
+#[no_mangle]
+pub extern "C" fn cfgSetLogCompress(enabled: bool) -> i32 {
+    logging::call("cfgSetLogCompress", None, || set_logging_config(|general_config| general_config.log_compress = enabled))
+}
 
-    general_config.http_proxy = Some(ffi::from_cstr(proxyUrl, proxyUrlLength));
-    general_config.http_proxy_username = Some(ffi::from_cstr(proxyUsername, proxyUsernameLength));
-    general_config.http_proxy_password = Some(ffi::from_cstr(proxyPassword, proxyPasswordLength));
+fn set_logging_config(set: impl FnOnce(&mut config::GeneralConfig)) -> i32 {
+    let previous = {
+        let mut general_config = ok_or_return!(config::GENERAL_CONFIG.lock(), |err| ReturnCode::Unknown.into());
+        let previous = general_config.clone();
 
-    ReturnCode::ExecutionOk.into()
+        set(&mut general_config);
+        previous
+    };
+
+    match logging::reconfigure_logging() {
+        Ok(_) => ReturnCode::ExecutionOk.into(),
+        Err(err) => {
+            log::error!("{}", err);
+
+            if let Ok(mut general_config) = config::GENERAL_CONFIG.lock() {
+                *general_config = previous;
+            }
+
+            match err {
+                logging::Error::InvalidLogFormat(_) | logging::Error::InvalidLogRotateAge(_) => ReturnCode::InvalidConfig.into(),
+                _ => ReturnCode::Unknown.into(),
+            }
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn cfgSetHttpProxy(proxyUrl: *const i8, proxyUrlLength: u32) -> i32 {
+    logging::call("cfgSetHttpProxy", None, || {
+        let mut general_config = ok_or_return!(config::GENERAL_CONFIG.lock(), |err| ReturnCode::Unknown.into());
+
+        general_config.http_proxy = Some(ffi::from_cstr(proxyUrl, proxyUrlLength));
+        backend::clear();
+
+        ReturnCode::ExecutionOk.into()
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn cfgSetHttpProxyWithUsernameAndPassword(proxyUrl: *const i8, proxyUrlLength: u32, proxyUsername: *const i8, proxyUsernameLength: u32, proxyPassword: *const i8, proxyPasswordLength: u32) -> i32 {
+    logging::call("cfgSetHttpProxyWithUsernameAndPassword", None, || {
+        let mut general_config = ok_or_return!(config::GENERAL_CONFIG.lock(), |err| ReturnCode::Unknown.into());
+
+        general_config.http_proxy = Some(ffi::from_cstr(proxyUrl, proxyUrlLength));
+        general_config.http_proxy_username = Some(ffi::from_cstr(proxyUsername, proxyUsernameLength));
+        general_config.http_proxy_password = Some(ffi::from_cstr(proxyPassword, proxyPasswordLength).into());
+        backend::clear();
+
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 /// `not implemented`
@@ -215,22 +286,26 @@ pub extern "C" fn cfgSetRetries(retries: u64) -> i32 {
     ReturnCode::NotImplemented.into()
 }
 
-/// `not implemented`
+/// Checks all TSEs every `interval` seconds, `0` disables the heartbeat.
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
 pub extern "C" fn cfgSetUploadMessageInterval(interval: u32) -> i32 {
-    log::info!("{}", "cfgSetUploadMessageInterval");
+    logging::call("cfgSetUploadMessageInterval", None, || {
+        ok_or_return!(config::GENERAL_CONFIG.lock(), |err| ReturnCode::Unknown.into()).msg_upload_interval = interval as u64;
 
-    ReturnCode::NotImplemented.into()
+        heartbeat::reschedule();
+
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
-/// `not implemented`
+/// Limits the audit log of each TSE to `maximum` MB, `0` disables the audit log.
 #[no_mangle]
-#[cfg(feature = "not_implemented")]
-#[cfg_attr(docsrs, doc(cfg(feature = "not_implemented")))]
 pub extern "C" fn cfgSetMaxAuditLogSize(maximum: u32) -> i32 {
-    log::info!("{}", "cfgSetMaxAuditLogSize");
+    logging::call("cfgSetMaxAuditLogSize", None, || {
+        let mut general_config = ok_or_return!(config::GENERAL_CONFIG.lock(), |err| ReturnCode::Unknown.into());
 
-    ReturnCode::NotImplemented.into()
+        general_config.max_audit_log_size = maximum;
+
+        ReturnCode::ExecutionOk.into()
+    })
 }
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 #[derive(Debug, Eq, PartialEq, TryFromPrimitive, IntoPrimitive, Copy, Clone)]
@@ -94,9 +96,17 @@ pub enum ReturnCode {
     NotImplemented = -6001,
 }
 
-impl ToString for ReturnCode {
-    fn to_string(&self) -> String {
-        let s = match self {
+#[derive(Debug, Eq, PartialEq, TryFromPrimitive, IntoPrimitive, Copy, Clone)]
+#[repr(u32)]
+pub enum Language {
+    English = 0,
+    German = 1,
+}
+
+impl ReturnCode {
+    /// Returns the name of the return code as defined in the A-Trust header files (e.g. `ERROR_TSE_TIMEOUT`).
+    pub fn name(&self) -> &'static str {
+        match self {
             ReturnCode::ExecutionOk => "EXECUTION_OK",
             ReturnCode::RetrieveLogMessageFailed => "ERROR_RETRIEVE_LOG_MESSAGE_FAILED",
             ReturnCode::StorageFailure => "ERROR_STORAGE_FAILURE",
@@ -186,8 +196,113 @@ impl ToString for ReturnCode {
             ReturnCode::Unknown => "ERROR_UNKNOWN",
             ReturnCode::UnsupportedPremiumFeature => "ERROR_UNSUPPORTED_PREMIUM_FEATURE",
             ReturnCode::NotImplemented => "ERROR_NOT_IMPLEMENTED",
+        }
+    }
+
+    /// Returns a human readable description of the return code in the given language.
+    pub fn description(&self, language: Language) -> &'static str {
+        let (en, de) = match self {
+            ReturnCode::ExecutionOk => ("The function was executed successfully.", "Die Funktion wurde erfolgreich ausgeführt."),
+            ReturnCode::RetrieveLogMessageFailed => ("The log message could not be retrieved from the TSE.", "Die Log-Nachricht konnte nicht von der TSE abgerufen werden."),
+            ReturnCode::StorageFailure => ("The log message could not be stored.", "Die Log-Nachricht konnte nicht gespeichert werden."),
+            ReturnCode::UpdateTimeFailed => ("The time of the TSE could not be updated.", "Die Zeit der TSE konnte nicht aktualisiert werden."),
+            ReturnCode::ParameterMismatch => ("The passed parameters do not match.", "Die übergebenen Parameter passen nicht zusammen."),
+            ReturnCode::IdNotFound => ("No data was found for the given client id.", "Für die angegebene Client-ID wurden keine Daten gefunden."),
+            ReturnCode::TransactionNumberNotFound => ("No data was found for the given transaction number.", "Für die angegebene Transaktionsnummer wurden keine Daten gefunden."),
+            ReturnCode::NoDataAvailable => ("No data is available for the given filter.", "Für den angegebenen Filter sind keine Daten verfügbar."),
+            ReturnCode::TooManyRecords => ("The number of records exceeds the given maximum.", "Die Anzahl der Datensätze überschreitet das angegebene Maximum."),
+            ReturnCode::StartTransactionFailed => ("The transaction could not be started.", "Die Transaktion konnte nicht gestartet werden."),
+            ReturnCode::UpdateTransactionFailed => ("The transaction could not be updated.", "Die Transaktion konnte nicht aktualisiert werden."),
+            ReturnCode::FinishTransactionFailed => ("The transaction could not be finished.", "Die Transaktion konnte nicht abgeschlossen werden."),
+            ReturnCode::RestoreFailed => ("Restoring from the backup failed.", "Die Wiederherstellung aus der Sicherung ist fehlgeschlagen."),
+            ReturnCode::StoringInitDataFailed => ("The initialization data could not be stored.", "Die Initialisierungsdaten konnten nicht gespeichert werden."),
+            ReturnCode::ExportCertFailed => ("The certificates could not be exported.", "Die Zertifikate konnten nicht exportiert werden."),
+            ReturnCode::NoLogMessage => ("There is no log message available.", "Es ist keine Log-Nachricht verfügbar."),
+            ReturnCode::ReadingLogMessage => ("The log message could not be read.", "Die Log-Nachricht konnte nicht gelesen werden."),
+            ReturnCode::NoTransaction => ("No transaction is known for the given transaction number.", "Zur angegebenen Transaktionsnummer ist keine Transaktion bekannt."),
+            ReturnCode::SeApiNotInitialized => ("The SE API has not been initialized.", "Die SE-API wurde nicht initialisiert."),
+            ReturnCode::TimeNotSet => ("The time of the TSE has not been set.", "Die Zeit der TSE wurde nicht gesetzt."),
+            ReturnCode::CertificateExpired => ("The certificate of the TSE has expired.", "Das Zertifikat der TSE ist abgelaufen."),
+            ReturnCode::SecureElementDisabled => ("The TSE has been disabled.", "Die TSE wurde außer Betrieb genommen."),
+            ReturnCode::UserNotAuthorized => ("The user is not authorized to execute this function.", "Der Benutzer ist nicht berechtigt, diese Funktion auszuführen."),
+            ReturnCode::UserNotAuthenticated => ("The user has not been authenticated.", "Der Benutzer wurde nicht authentifiziert."),
+            ReturnCode::DescriptionNotSetByManufacturer => ("The description of the SE API has not been set by the manufacturer.", "Die Beschreibung der SE-API wurde nicht vom Hersteller gesetzt."),
+            ReturnCode::DescriptionSetByManufacturer => ("The description of the SE API has already been set by the manufacturer.", "Die Beschreibung der SE-API wurde bereits vom Hersteller gesetzt."),
+            ReturnCode::ExportSerialNumbersFailed => ("The serial numbers could not be exported.", "Die Seriennummern konnten nicht exportiert werden."),
+            ReturnCode::GetMaxNumberOfClientsFailed => ("The maximum number of clients could not be determined.", "Die maximale Anzahl an Clients konnte nicht ermittelt werden."),
+            ReturnCode::GetCurrentNumberOfClientsFailed => ("The current number of clients could not be determined.", "Die aktuelle Anzahl an Clients konnte nicht ermittelt werden."),
+            ReturnCode::GetMaxNumberTransactionsFailed => ("The maximum number of transactions could not be determined.", "Die maximale Anzahl an Transaktionen konnte nicht ermittelt werden."),
+            ReturnCode::GetCurrentNumberOfTransactionsFailed => ("The current number of open transactions could not be determined.", "Die aktuelle Anzahl an offenen Transaktionen konnte nicht ermittelt werden."),
+            ReturnCode::GetSupportedUpdateVariantsFailed => ("The supported transaction update variants could not be determined.", "Die unterstützten Varianten zur Aktualisierung von Transaktionen konnten nicht ermittelt werden."),
+            ReturnCode::DeleteStoredDataFailed => ("The stored data could not be deleted.", "Die gespeicherten Daten konnten nicht gelöscht werden."),
+            ReturnCode::UnexportedStoredData => ("The stored data has not been exported yet.", "Die gespeicherten Daten wurden noch nicht exportiert."),
+            ReturnCode::SigningSystemOperationDataFailed => ("The system operation data could not be signed.", "Die Systemdaten konnten nicht signiert werden."),
+            ReturnCode::UserIdNotManaged => ("The user id is not managed by the SE API.", "Die Benutzer-ID wird von der SE-API nicht verwaltet."),
+            ReturnCode::DisableSecureElementFailed => ("The TSE could not be disabled.", "Die TSE konnte nicht außer Betrieb genommen werden."),
+            ReturnCode::ConfigValueNotFound => ("A required configuration value was not found.", "Ein benötigter Konfigurationswert wurde nicht gefunden."),
+            ReturnCode::InvalidConfig => ("The configuration is invalid.", "Die Konfiguration ist ungültig."),
+            ReturnCode::SuspendSecureElementFailed => ("The TSE could not be suspended.", "Die TSE konnte nicht gesperrt werden."),
+            ReturnCode::UnsuspendSecureElementFailed => ("The TSE could not be unsuspended.", "Die Sperre der TSE konnte nicht aufgehoben werden."),
+            ReturnCode::GetOpenTransactionsFailed => ("The open transactions could not be determined.", "Die offenen Transaktionen konnten nicht ermittelt werden."),
+            ReturnCode::GetLifecycleStateFailed => ("The lifecycle state of the TSE could not be determined.", "Der Lebenszyklus-Status der TSE konnte nicht ermittelt werden."),
+            ReturnCode::GetTransactionCounterFailed => ("The transaction counter could not be determined.", "Der Transaktionszähler konnte nicht ermittelt werden."),
+            ReturnCode::GetSignatureCounterFailed => ("The signature counter could not be determined.", "Der Signaturzähler konnte nicht ermittelt werden."),
+            ReturnCode::GetTotalLogMemory => ("The total log memory could not be determined.", "Die Größe des Log-Speichers konnte nicht ermittelt werden."),
+            ReturnCode::GetLogTimeFormat => ("The log time format could not be determined.", "Das Zeitformat der Logs konnte nicht ermittelt werden."),
+            ReturnCode::ExportPublicKeyFailed => ("The public key could not be exported.", "Der öffentliche Schlüssel konnte nicht exportiert werden."),
+            ReturnCode::ExportCertificateFailed => ("The certificate could not be exported.", "Das Zertifikat konnte nicht exportiert werden."),
+            ReturnCode::TpmConnect => ("The connection to the TPM could not be established.", "Die Verbindung zum TPM konnte nicht hergestellt werden."),
+            ReturnCode::InvalidClientId => ("The client id is invalid.", "Die Client-ID ist ungültig."),
+            ReturnCode::ClientIdNotRegistered => ("The client id is not registered.", "Die Client-ID ist nicht registriert."),
+            ReturnCode::ClientIdRegistrationFailed => ("The client id could not be registered.", "Die Client-ID konnte nicht registriert werden."),
+            ReturnCode::CannotRetrieveRegisteredClientIds => ("The registered client ids could not be retrieved.", "Die registrierten Client-IDs konnten nicht abgerufen werden."),
+            ReturnCode::CorruptedRegisteredClientIds => ("The registered client ids are corrupted.", "Die registrierten Client-IDs sind beschädigt."),
+            ReturnCode::CorruptedAppData => ("The application data is corrupted.", "Die Anwendungsdaten sind beschädigt."),
+            ReturnCode::SetPinsFailed => ("The PINs could not be set.", "Die PINs konnten nicht gesetzt werden."),
+            ReturnCode::SeAlreadyInitialized => ("The TSE has already been initialized.", "Die TSE wurde bereits initialisiert."),
+            ReturnCode::GetSignatureAlgorithmFailed => ("The signature algorithm could not be determined.", "Der Signaturalgorithmus konnte nicht ermittelt werden."),
+            ReturnCode::AuthenticationFailed => ("The authentication failed.", "Die Authentifizierung ist fehlgeschlagen."),
+            ReturnCode::UnblockFailed => ("The user could not be unblocked.", "Der Benutzer konnte nicht entsperrt werden."),
+            ReturnCode::MissingParameter => ("A required parameter is missing.", "Ein benötigter Parameter fehlt."),
+            ReturnCode::FunctionNotSupported => ("The function is not supported.", "Die Funktion wird nicht unterstützt."),
+            ReturnCode::Io => ("An input/output error occurred.", "Ein Ein-/Ausgabefehler ist aufgetreten."),
+            ReturnCode::TseTimeout => ("The TSE did not respond in time.", "Die TSE hat nicht rechtzeitig geantwortet."),
+            ReturnCode::AllocationFailed => ("Memory could not be allocated.", "Speicher konnte nicht reserviert werden."),
+            ReturnCode::ConfigFileNotFound => ("The configuration file was not found.", "Die Konfigurationsdatei wurde nicht gefunden."),
+            ReturnCode::SeCommunicationFailed => ("The communication with the TSE failed.", "Die Kommunikation mit der TSE ist fehlgeschlagen."),
+            ReturnCode::TseCommandDataInvalid => ("The command data sent to the TSE is invalid.", "Die an die TSE gesendeten Kommandodaten sind ungültig."),
+            ReturnCode::TseResponseDataInvalid => ("The response data received from the TSE is invalid.", "Die von der TSE empfangenen Antwortdaten sind ungültig."),
+            ReturnCode::ErsAlreadyMapped => ("The ERS is already mapped.", "Das ERS ist bereits zugeordnet."),
+            ReturnCode::NoErs => ("No ERS is available.", "Es ist kein ERS verfügbar."),
+            ReturnCode::TseUnknownError => ("The TSE reported an unknown error.", "Die TSE hat einen unbekannten Fehler gemeldet."),
+            ReturnCode::StreamWrite => ("Writing to the stream failed.", "Das Schreiben in den Stream ist fehlgeschlagen."),
+            ReturnCode::BufferTooSmall => ("The provided buffer is too small.", "Der übergebene Puffer ist zu klein."),
+            ReturnCode::NoSuchKey => ("The key does not exist.", "Der Schlüssel existiert nicht."),
+            ReturnCode::NoKey => ("No key is available.", "Es ist kein Schlüssel verfügbar."),
+            ReturnCode::SeApiDeactivated => ("The SE API has been deactivated.", "Die SE-API wurde deaktiviert."),
+            ReturnCode::SeApiNotDeactivated => ("The SE API has not been deactivated.", "Die SE-API wurde nicht deaktiviert."),
+            ReturnCode::AtLoadNotCalled => ("at_load has not been called.", "at_load wurde nicht aufgerufen."),
+            ReturnCode::SeIpcProtocolError => ("An error occurred in the IPC protocol of the SE API.", "Im IPC-Protokoll der SE-API ist ein Fehler aufgetreten."),
+            ReturnCode::AtSetPinsFailed => ("at_setPins failed.", "at_setPins ist fehlgeschlagen."),
+            ReturnCode::SeNotProvisionedError => ("The TSE has not been provisioned.", "Die TSE wurde nicht provisioniert."),
+            ReturnCode::SeAlreadyProvisioned => ("The TSE has already been provisioned.", "Die TSE wurde bereits provisioniert."),
+            ReturnCode::SeInSecureState => ("The TSE is in a secure state.", "Die TSE befindet sich in einem sicheren Zustand."),
+            ReturnCode::Unknown => ("An unknown error occurred.", "Ein unbekannter Fehler ist aufgetreten."),
+            ReturnCode::UnsupportedPremiumFeature => ("The feature requires a premium licence.", "Die Funktion erfordert eine Premium-Lizenz."),
+            ReturnCode::NotImplemented => ("The function is not implemented.", "Die Funktion ist nicht implementiert."),
         };
 
-        format!("{} ({})", s, *self as i32)
+        match language {
+            Language::English => en,
+            Language::German => de,
+        }
     }
 }
+
+impl fmt::Display for ReturnCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name(), *self as i32)
+    }
+}
+
+impl std::error::Error for ReturnCode {}
@@ -5,12 +5,7 @@
 use log::error;
 use num_enum::IntoPrimitive;
 
-use crate::{
-    atrustapi::return_codes::ReturnCode,
-    client::{self, Client},
-    helpers::ffi,
-    idesscd::*,
-};
+use crate::{atrustapi::return_codes::ReturnCode, backend, helpers::ffi, idesscd::*, logging};
 
 const MAX_CHUNK_SIZE: i32 = 1000;
 
@@ -74,14 +69,16 @@ pub extern "C" fn initializeDescriptionSet() -> i32 {
 
 #[no_mangle]
 pub extern "C" fn initializeDescriptionSetWithTse(configEntry: *const i8, configEntryLength: u32) -> i32 {
-    let tse_state = TseState { current_state: TseStates::Initialized };
+    logging::call("initializeDescriptionSetWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let tse_state = TseState { current_state: TseStates::Initialized };
 
-    try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength))?.set_tse_state(&tse_state), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
+        try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength))?.set_tse_state(&tse_state), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
 
-    ReturnCode::ExecutionOk.into()
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -105,12 +102,14 @@ pub extern "C" fn updateTimeWithTimeSync() -> i32 {
 
 #[no_mangle]
 pub extern "C" fn updateTimeWithTimeSyncWithTse(configEntry: *const i8, configEntryLength: u32) -> i32 {
-    try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength))?.execute_set_tse_time(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
+    logging::call("updateTimeWithTimeSyncWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength))?.execute_set_tse_time(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
 
-    ReturnCode::ExecutionOk.into()
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -122,14 +121,16 @@ pub extern "C" fn disableSecureElement() -> i32 {
 
 #[no_mangle]
 pub extern "C" fn disableSecureElementWithTse(configEntry: *const i8, configEntryLength: u32) -> i32 {
-    let tse_state = TseState { current_state: TseStates::Terminated };
+    logging::call("disableSecureElementWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let tse_state = TseState { current_state: TseStates::Terminated };
 
-    try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength))?.set_tse_state(&tse_state), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
+        try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength))?.set_tse_state(&tse_state), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
 
-    ReturnCode::ExecutionOk.into()
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -193,41 +194,43 @@ pub unsafe extern "C" fn startTransactionWithTse(
     configEntry: *const i8,
     configEntryLength: u32,
 ) -> i32 {
-    let start_transaction_request = StartTransactionRequest {
-        client_id: ffi::from_cstr(clientId, clientIdLength),
-        process_type: ffi::from_cstr(processType, processTypeLength),
-        process_data_base64: Base64::from(ffi::from_cba(processData, processDataLength)),
-        queue_item_id: uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_URL, "fiskaltrust.eu".as_bytes()),
-        is_retry: false,
-    };
-
-    let StartTransactionResponse {
-        transaction_number,
-        time_stamp,
-        tse_serial_number_octet,
-        client_id,
-        signature_data,
-    } = try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength))?.start_transaction(&start_transaction_request), |err: client::Error| {
-        error!("{}", err);
-        match err {
-            client::Error::Unsuccessful(_) => ReturnCode::StartTransactionFailed,
-            err => Into::<ReturnCode>::into(err),
-        }
-        .into()
-    });
-
-    ffi::set_u32_ptr(transactionNumber, transaction_number as u32);
-    ffi::set_i64_ptr(logTime, time_stamp.timestamp());
-    ffi::set_u32_ptr(signatureCounter, signature_data.signature_counter as u32);
-    ffi::set_cstr(serialNumber, serialNumberLength, tse_serial_number_octet);
-    let signature_value = try_or_return!(|| signature_data.signature_base64.decode(), |err| {
-        error!("{}", err);
-        ReturnCode::RetrieveLogMessageFailed.into()
-    });
-    ffi::set_byte_buf(signatureValue, signature_value.as_slice());
-    ffi::set_u32_ptr(signatureValueLength, signature_value.len() as u32);
+    logging::call("startTransactionWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let start_transaction_request = StartTransactionRequest {
+            client_id: ffi::from_cstr(clientId, clientIdLength),
+            process_type: ffi::from_cstr(processType, processTypeLength),
+            process_data_base64: Base64::from(ffi::from_cba(processData, processDataLength)),
+            queue_item_id: uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_URL, "fiskaltrust.eu".as_bytes()),
+            is_retry: false,
+        };
+
+        let StartTransactionResponse {
+            transaction_number,
+            time_stamp,
+            tse_serial_number_octet,
+            client_id,
+            signature_data,
+        } = try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength))?.start_transaction(&start_transaction_request), |err: backend::Error| {
+            error!("{}", err);
+            match err {
+                backend::Error::Unsuccessful(_) => ReturnCode::StartTransactionFailed,
+                err => Into::<ReturnCode>::into(err),
+            }
+            .into()
+        });
 
-    ReturnCode::ExecutionOk.into()
+        ffi::set_u32_ptr(transactionNumber, transaction_number as u32);
+        ffi::set_i64_ptr(logTime, time_stamp.timestamp());
+        ffi::set_u32_ptr(signatureCounter, signature_data.signature_counter as u32);
+        ffi::set_cstr(serialNumber, serialNumberLength, tse_serial_number_octet);
+        let signature_value = try_or_return!(|| signature_data.signature_base64.decode(), |err| {
+            error!("{}", err);
+            ReturnCode::RetrieveLogMessageFailed.into()
+        });
+        ffi::set_byte_buf(signatureValue, signature_value.as_slice());
+        ffi::set_u32_ptr(signatureValueLength, signature_value.len() as u32);
+
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -279,34 +282,36 @@ pub unsafe extern "C" fn updateTransactionWithTse(
     configEntry: *const i8,
     configEntryLength: u32,
 ) -> i32 {
-    let update_transaction_request = UpdateTransactionRequest {
-        client_id: ffi::from_cstr(clientId, clientIdLength),
-        process_type: ffi::from_cstr(processType, processTypeLength),
-        process_data_base64: Base64::from(ffi::from_cba(processData, processDataLength)),
-        queue_item_id: uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_URL, "fiskaltrust.eu".as_bytes()),
-        is_retry: false,
-        transaction_number: transactionNumber as u64,
-    };
-
-    let update_transaction_response = try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength))?.update_transaction(&update_transaction_request), |err: client::Error| {
-        error!("{}", err);
-        match err {
-            client::Error::Unsuccessful(_) => ReturnCode::UpdateTransactionFailed,
-            err => Into::<ReturnCode>::into(err),
-        }
-        .into()
-    });
-
-    ffi::set_i64_ptr(logTime, update_transaction_response.time_stamp.timestamp());
-    ffi::set_u32_ptr(signatureCounter, update_transaction_response.signature_data.signature_counter as u32);
-    let signature_value = try_or_return!(|| update_transaction_response.signature_data.signature_base64.decode(), |err| {
-        error!("{}", err);
-        ReturnCode::RetrieveLogMessageFailed.into()
-    });
-    ffi::set_byte_buf(signatureValue, signature_value.as_slice());
-    ffi::set_u32_ptr(signatureValueLength, signature_value.len() as u32);
+    logging::call("updateTransactionWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let update_transaction_request = UpdateTransactionRequest {
+            client_id: ffi::from_cstr(clientId, clientIdLength),
+            process_type: ffi::from_cstr(processType, processTypeLength),
+            process_data_base64: Base64::from(ffi::from_cba(processData, processDataLength)),
+            queue_item_id: uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_URL, "fiskaltrust.eu".as_bytes()),
+            is_retry: false,
+            transaction_number: transactionNumber as u64,
+        };
+
+        let update_transaction_response = try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength))?.update_transaction(&update_transaction_request), |err: backend::Error| {
+            error!("{}", err);
+            match err {
+                backend::Error::Unsuccessful(_) => ReturnCode::UpdateTransactionFailed,
+                err => Into::<ReturnCode>::into(err),
+            }
+            .into()
+        });
 
-    ReturnCode::ExecutionOk.into()
+        ffi::set_i64_ptr(logTime, update_transaction_response.time_stamp.timestamp());
+        ffi::set_u32_ptr(signatureCounter, update_transaction_response.signature_data.signature_counter as u32);
+        let signature_value = try_or_return!(|| update_transaction_response.signature_data.signature_base64.decode(), |err| {
+            error!("{}", err);
+            ReturnCode::RetrieveLogMessageFailed.into()
+        });
+        ffi::set_byte_buf(signatureValue, signature_value.as_slice());
+        ffi::set_u32_ptr(signatureValueLength, signature_value.len() as u32);
+
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -364,34 +369,36 @@ pub unsafe extern "C" fn finishTransactionWithTse(
     configEntry: *const i8,
     configEntryLength: u32,
 ) -> i32 {
-    let finish_transaction_request = FinishTransactionRequest {
-        client_id: ffi::from_cstr(clientId, clientIdLength),
-        process_type: ffi::from_cstr(processType, processTypeLength),
-        process_data_base64: Base64::from(ffi::from_cba(processData, processDataLength)),
-        queue_item_id: uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_URL, "fiskaltrust.eu".as_bytes()),
-        is_retry: false,
-        transaction_number: transactionNumber as u64,
-    };
-
-    let finish_transaction_response = try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength))?.finish_transaction(&finish_transaction_request), |err: client::Error| {
-        error!("{}", err);
-        match err {
-            client::Error::Unsuccessful(_) => ReturnCode::FinishTransactionFailed,
-            err => Into::<ReturnCode>::into(err),
-        }
-        .into()
-    });
-
-    ffi::set_i64_ptr(logTime, finish_transaction_response.time_stamp.timestamp());
-    ffi::set_u32_ptr(signatureCounter, finish_transaction_response.signature_data.signature_counter as u32);
-    let signature_value = try_or_return!(|| finish_transaction_response.signature_data.signature_base64.decode(), |err| {
-        error!("{}", err);
-        ReturnCode::RetrieveLogMessageFailed.into()
-    });
-    ffi::set_byte_buf(signatureValue, signature_value.as_slice());
-    ffi::set_u32_ptr(signatureValueLength, signature_value.len() as u32);
+    logging::call("finishTransactionWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let finish_transaction_request = FinishTransactionRequest {
+            client_id: ffi::from_cstr(clientId, clientIdLength),
+            process_type: ffi::from_cstr(processType, processTypeLength),
+            process_data_base64: Base64::from(ffi::from_cba(processData, processDataLength)),
+            queue_item_id: uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_URL, "fiskaltrust.eu".as_bytes()),
+            is_retry: false,
+            transaction_number: transactionNumber as u64,
+        };
+
+        let finish_transaction_response = try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength))?.finish_transaction(&finish_transaction_request), |err: backend::Error| {
+            error!("{}", err);
+            match err {
+                backend::Error::Unsuccessful(_) => ReturnCode::FinishTransactionFailed,
+                err => Into::<ReturnCode>::into(err),
+            }
+            .into()
+        });
 
-    ReturnCode::ExecutionOk.into()
+        ffi::set_i64_ptr(logTime, finish_transaction_response.time_stamp.timestamp());
+        ffi::set_u32_ptr(signatureCounter, finish_transaction_response.signature_data.signature_counter as u32);
+        let signature_value = try_or_return!(|| finish_transaction_response.signature_data.signature_base64.decode(), |err| {
+            error!("{}", err);
+            ReturnCode::RetrieveLogMessageFailed.into()
+        });
+        ffi::set_byte_buf(signatureValue, signature_value.as_slice());
+        ffi::set_u32_ptr(signatureValueLength, signature_value.len() as u32);
+
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 /// `not implemented`
@@ -542,60 +549,62 @@ pub unsafe extern "C" fn exportDataWithClientId(clientId: *const i8, clientIdLen
 }
 
 pub unsafe extern "C" fn exportDataWithClientIdWithTse(clientId: *const i8, clientIdLength: u32, exportedData: *mut *mut u8, exportedDataLength: *mut u32, configEntry: *const i8, configEntryLength: u32) -> i32 {
-    let client = try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength)), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
-
-    let start_export_session_request = StartExportSessionRequest {
-        client_id: ffi::from_cstr(clientId, clientIdLength),
-        erase: false,
-    };
-
-    let start_export_session_response = try_or_return!(|| client.start_export_session(&start_export_session_request), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
-
-    let export_data_request = ExportDataRequest {
-        token_id: start_export_session_response.token_id.clone(),
-        max_chunk_size: MAX_CHUNK_SIZE,
-    };
-
-    let mut export_data: Vec<u8> = vec![];
-
-    loop {
-        let export_data_response = try_or_return!(|| client.export_data(&export_data_request), |err: client::Error| {
+    logging::call("exportDataWithClientIdWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let client = try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength)), |err: backend::Error| {
             error!("{}", err);
             Into::<ReturnCode>::into(err).into()
         });
 
-        export_data.extend_from_slice(export_data_response.tar_file_byte_chunk_base64.as_bytes());
+        let start_export_session_request = StartExportSessionRequest {
+            client_id: ffi::from_cstr(clientId, clientIdLength),
+            erase: false,
+        };
 
-        if export_data_response.tar_file_end_of_file {
-            break;
+        let start_export_session_response = try_or_return!(|| client.start_export_session(&start_export_session_request), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
+
+        let export_data_request = ExportDataRequest {
+            token_id: start_export_session_response.token_id.clone(),
+            max_chunk_size: MAX_CHUNK_SIZE,
+        };
+
+        let mut export_data: Vec<u8> = vec![];
+
+        loop {
+            let export_data_response = try_or_return!(|| client.export_data(&export_data_request), |err: backend::Error| {
+                error!("{}", err);
+                Into::<ReturnCode>::into(err).into()
+            });
+
+            export_data.extend_from_slice(export_data_response.tar_file_byte_chunk_base64.as_str().as_bytes());
+
+            if export_data_response.tar_file_end_of_file {
+                break;
+            }
         }
-    }
 
-    let end_export_session_request = EndExportSessionRequest {
-        token_id: start_export_session_response.token_id,
-        sha256_checksum_base64: sha256::digest(export_data.as_slice()),
-        erase: false,
-    };
+        let end_export_session_request = EndExportSessionRequest {
+            token_id: start_export_session_response.token_id,
+            sha256_checksum_base64: sha256::digest(export_data.as_slice()),
+            erase: false,
+        };
 
-    let end_export_session_response = try_or_return!(|| client.end_export_session(&end_export_session_request), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
+        let end_export_session_response = try_or_return!(|| client.end_export_session(&end_export_session_request), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
 
-    if !end_export_session_response.is_valid {
-        return ReturnCode::Unknown.into();
-    }
+        if !end_export_session_response.is_valid {
+            return ReturnCode::Unknown.into();
+        }
 
-    ffi::set_byte_buf(exportedData, export_data.as_slice());
-    ffi::set_u32_ptr(exportedDataLength, export_data.len() as u32);
+        ffi::set_byte_buf(exportedData, export_data.as_slice());
+        ffi::set_u32_ptr(exportedDataLength, export_data.len() as u32);
 
-    ReturnCode::ExecutionOk.into()
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -667,13 +676,15 @@ pub unsafe extern "C" fn getMaxNumberOfClients(maxNumberClients: *mut u32) -> i3
 
 #[no_mangle]
 pub unsafe extern "C" fn getMaxNumberOfClientsWithTse(maxNumberClients: *mut u32, configEntry: *const i8, configEntryLength: u32) -> i32 {
-    let tse_info = try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength))?.get_tse_info(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
+    logging::call("getMaxNumberOfClientsWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let tse_info = try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength))?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
 
-    ffi::set_u32_ptr(maxNumberClients, tse_info.max_number_of_clients as u32);
-    ReturnCode::ExecutionOk.into()
+        ffi::set_u32_ptr(maxNumberClients, tse_info.max_number_of_clients as u32);
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -685,13 +696,15 @@ pub unsafe extern "C" fn getCurrentNumberOfClients(currentNumberClients: *mut u3
 
 #[no_mangle]
 pub unsafe extern "C" fn getCurrentNumberOfClientsWithTse(currentNumberClients: *mut u32, configEntry: *const i8, configEntryLength: u32) -> i32 {
-    let tse_info = try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength))?.get_tse_info(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
+    logging::call("getCurrentNumberOfClientsWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let tse_info = try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength))?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
 
-    ffi::set_u32_ptr(currentNumberClients, tse_info.current_number_of_clients as u32);
-    ReturnCode::ExecutionOk.into()
+        ffi::set_u32_ptr(currentNumberClients, tse_info.current_number_of_clients as u32);
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -703,13 +716,15 @@ pub unsafe extern "C" fn getMaxNumberOfTransactions(maxNumberTransactions: *mut
 
 #[no_mangle]
 pub unsafe extern "C" fn getMaxNumberOfTransactionsWithTse(maxNumberTransactions: *mut u32, configEntry: *const i8, configEntryLength: u32) -> i32 {
-    let tse_info = try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength))?.get_tse_info(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
+    logging::call("getMaxNumberOfTransactionsWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let tse_info = try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength))?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
 
-    ffi::set_u32_ptr(maxNumberTransactions, tse_info.max_number_of_started_transactions as u32);
-    ReturnCode::ExecutionOk.into()
+        ffi::set_u32_ptr(maxNumberTransactions, tse_info.max_number_of_started_transactions as u32);
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -721,13 +736,15 @@ pub unsafe extern "C" fn getCurrentNumberOfTransactions(currentNumberTransaction
 
 #[no_mangle]
 pub unsafe extern "C" fn getCurrentNumberOfTransactionsWithTse(currentNumberTransactions: *mut u32, configEntry: *const i8, configEntryLength: u32) -> i32 {
-    let tse_info = try_or_return!(|| Client::get(ffi::from_cstr(configEntry, configEntryLength))?.get_tse_info(), |err: client::Error| {
-        error!("{}", err);
-        Into::<ReturnCode>::into(err).into()
-    });
+    logging::call("getCurrentNumberOfTransactionsWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let tse_info = try_or_return!(|| backend::get(ffi::from_cstr(configEntry, configEntryLength))?.get_tse_info(), |err: backend::Error| {
+            error!("{}", err);
+            Into::<ReturnCode>::into(err).into()
+        });
 
-    ffi::set_u32_ptr(currentNumberTransactions, tse_info.current_number_of_started_transactions as u32);
-    ReturnCode::ExecutionOk.into()
+        ffi::set_u32_ptr(currentNumberTransactions, tse_info.current_number_of_started_transactions as u32);
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 #[no_mangle]
@@ -739,8 +756,10 @@ pub extern "C" fn getSupportedTransactionUpdateVariants(supportedUpdateVariants:
 
 #[no_mangle]
 pub extern "C" fn getSupportedTransactionUpdateVariantsWithTse(supportedUpdateVariants: *mut UpdateVariants, configEntry: *const i8, configEntryLength: u32) -> i32 {
-    unsafe { ffi::set_u32_ptr(supportedUpdateVariants as *mut u32, UpdateVariants::SignedAndUnsigned.into()) }
-    ReturnCode::ExecutionOk.into()
+    logging::call("getSupportedTransactionUpdateVariantsWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        unsafe { ffi::set_u32_ptr(supportedUpdateVariants as *mut u32, UpdateVariants::SignedAndUnsigned.into()) }
+        ReturnCode::ExecutionOk.into()
+    })
 }
 
 /// `not implemented`
@@ -762,15 +781,37 @@ pub extern "C" fn deleteStoredDataWithTse(configEntry: *const i8, configEntryLen
 }
 
 #[no_mangle]
-pub extern "C" fn authenticateUser(userId: *const i8, userIdLength: u32, pin: *const u8, pinLength: u32, authenticationResult: *mut AuthenticationResult, remainingRetries: *mut i16) -> i32 {
+pub unsafe extern "C" fn authenticateUser(userId: *const i8, userIdLength: u32, pin: *const u8, pinLength: u32, authenticationResult: *mut AuthenticationResult, remainingRetries: *mut i16) -> i32 {
     log::info!("{}", "authenticateUser");
 
     authenticateUserWithTse(userId, userIdLength, pin, pinLength, authenticationResult, remainingRetries, b"default".as_ptr() as *const i8, "default".len() as u32)
 }
 
 #[no_mangle]
-pub extern "C" fn authenticateUserWithTse(userId: *const i8, userIdLength: u32, pin: *const u8, pinLength: u32, authenticationResult: *mut AuthenticationResult, remainingRetries: *mut i16, configEntry: *const i8, configEntryLength: u32) -> i32 {
-    ReturnCode::ExecutionOk.into()
+pub unsafe extern "C" fn authenticateUserWithTse(userId: *const i8, userIdLength: u32, pin: *const u8, pinLength: u32, authenticationResult: *mut AuthenticationResult, remainingRetries: *mut i16, configEntry: *const i8, configEntryLength: u32) -> i32 {
+    logging::call("authenticateUserWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let (user_id, tse_id) = (ffi::from_cstr(userId, userIdLength), ffi::from_cstr(configEntry, configEntryLength));
+
+        let (return_code, authentication_result, remaining_retries) = if user_id != "Admin" {
+            error!("unknown user {}, only the Admin user can be authenticated", user_id);
+            (ReturnCode::ExecutionOk, AuthenticationResult::UnknownUserId, 0)
+        } else if pin.is_null() || pinLength == 0 {
+            error!("pin must not be empty");
+            (ReturnCode::MissingParameter, AuthenticationResult::Failed, 0)
+        } else {
+            match super::asigntse::authenticate_admin(&tse_id, ffi::from_cba(pin, pinLength)) {
+                Some((authentication_result, remaining_retries)) => (ReturnCode::ExecutionOk, authentication_result, remaining_retries),
+                None => {
+                    error!("no admin pin set for {}, call at_setPins first", tse_id);
+                    (ReturnCode::ExecutionOk, AuthenticationResult::Failed, 0)
+                }
+            }
+        };
+
+        ffi::set_i32_ptr(authenticationResult as *mut i32, authentication_result.into());
+        ffi::set_i16_ptr(remainingRetries, remaining_retries);
+        return_code.into()
+    })
 }
 
 #[no_mangle]
@@ -794,5 +835,29 @@ pub extern "C" fn unblockUser(userId: *const i8, userIdLength: u32, puk: *const
 
 #[no_mangle]
 pub extern "C" fn unblockUserWithTse(userId: *const i8, userIdLength: u32, puk: *const i8, pukLength: u32, newPin: *const i8, newPinLength: u32, unblockResult: *mut UnblockResult, configEntry: *const i8, configEntryLength: u32) -> i32 {
-    ReturnCode::ExecutionOk.into()
+    logging::call("unblockUserWithTse", Some(&ffi::from_cstr(configEntry, configEntryLength)), || {
+        let (user_id, tse_id) = (ffi::from_cstr(userId, userIdLength), ffi::from_cstr(configEntry, configEntryLength));
+
+        let (return_code, unblock_result) = if user_id != "Admin" {
+            error!("unknown user {}, only the Admin user can be unblocked", user_id);
+            (ReturnCode::ExecutionOk, UnblockResult::UnknownUserId)
+        } else if puk.is_null() || pukLength == 0 || newPin.is_null() || newPinLength == 0 {
+            error!("puk and newPin must not be empty");
+            (ReturnCode::MissingParameter, UnblockResult::Error)
+        } else if !super::asigntse::ADMIN_PIN_LENGTH.contains(&(newPinLength as usize)) {
+            error!("newPin must be {:?} bytes long", super::asigntse::ADMIN_PIN_LENGTH);
+            (ReturnCode::ParameterMismatch, UnblockResult::Error)
+        } else {
+            match unsafe { super::asigntse::unblock_admin(&tse_id, ffi::from_cba(puk as *const u8, pukLength), ffi::from_cba(newPin as *const u8, newPinLength)) } {
+                Some(unblock_result) => (ReturnCode::ExecutionOk, unblock_result),
+                None => {
+                    error!("no admin pin set for {}, call at_setPins first", tse_id);
+                    (ReturnCode::ExecutionOk, UnblockResult::Failed)
+                }
+            }
+        };
+
+        unsafe { ffi::set_u32_ptr(unblockResult as *mut u32, unblock_result.into()) };
+        return_code.into()
+    })
 }
@@ -0,0 +1,291 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::Serialize;
+
+use crate::{
+    backend::{Backend, Error},
+    config,
+    idesscd::*,
+    logging,
+};
+
+/// Serializes the writes to the audit logs, rotating a file must not interleave with appending to it.
+static AUDIT_LOG: Mutex<()> = Mutex::new(());
+
+/// A line of the audit log. Process data is recorded as sent so disputes can be reconstructed, with its length and hash to check it against the signed data.
+#[derive(Serialize, Default)]
+struct Entry<'a> {
+    timestamp: String,
+    tse_id: &'a str,
+    operation: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    correlation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    process_type: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    process_data_base64: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    process_data_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    process_data_sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transaction_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature_counter: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_id: Option<String>,
+    result: String,
+}
+
+impl<'a> Entry<'a> {
+    fn process_data(mut self, process_data_base64: &'a Base64) -> Entry<'a> {
+        let process_data = process_data_base64.decode().unwrap_or_default();
+
+        self.process_data_base64 = Some(process_data_base64.as_str());
+        self.process_data_length = Some(process_data.len());
+        self.process_data_sha256 = Some(sha256::digest(process_data.as_slice()));
+        self
+    }
+}
+
+/// The audit log is rotated once it reaches `1 / ROTATED_FILES` of `max_audit_log_size`, so dropping the oldest rotated file loses little of the history.
+const ROTATED_FILES: u64 = 8;
+
+/// The audit log of a TSE, `<log_dir>/audit/<tse_id>.log` for `0` and the files it is rotated to `<tse_id>.1.log`, `<tse_id>.2.log`, ... from the newest to the oldest.
+fn path(audit_dir: &Path, tse_id: &str, index: usize) -> PathBuf {
+    match index {
+        0 => audit_dir.join(format!("{}.log", tse_id)),
+        index => audit_dir.join(format!("{}.{}.log", tse_id, index)),
+    }
+}
+
+fn size(path: &Path) -> u64 {
+    fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0)
+}
+
+/// Appends the entry to the audit log of its TSE. Once the log would exceed an eighth of `max_audit_log_size` MB it is rotated, the oldest rotated files are only dropped if all of them together would exceed `max_audit_log_size` MB.
+fn write(entry: &Entry) -> Result<(), Box<dyn std::error::Error>> {
+    let (log_dir, max_audit_log_size) = config::GENERAL_CONFIG
+        .lock()
+        .map(|general_config| (general_config.log_dir.clone(), general_config.max_audit_log_size))
+        .unwrap_or_else(|_| (String::from("."), config::DEFAULT_MAX_AUDIT_LOG_SIZE));
+
+    if max_audit_log_size == 0 {
+        return Ok(());
+    }
+
+    let max_size = max_audit_log_size as u64 * 1024 * 1024;
+    let line = format!("{}\n", serde_json::to_string(entry)?);
+    let audit_dir = PathBuf::from(log_dir).join("audit");
+
+    let _lock = AUDIT_LOG.lock().map_err(|err| err.to_string())?;
+
+    fs::create_dir_all(&audit_dir)?;
+
+    let mut rotated = (1..).take_while(|index| path(&audit_dir, entry.tse_id, *index).exists()).count();
+    let size_of_log = size(&path(&audit_dir, entry.tse_id, 0));
+
+    if size_of_log > 0 && size_of_log + line.len() as u64 > max_size / ROTATED_FILES {
+        for index in (0..=rotated).rev() {
+            fs::rename(path(&audit_dir, entry.tse_id, index), path(&audit_dir, entry.tse_id, index + 1))?;
+        }
+
+        rotated += 1;
+    }
+
+    let mut total = line.len() as u64 + (0..=rotated).map(|index| size(&path(&audit_dir, entry.tse_id, index))).sum::<u64>();
+
+    while total > max_size && rotated > 0 {
+        let oldest = path(&audit_dir, entry.tse_id, rotated);
+
+        total -= size(&oldest);
+        fs::remove_file(&oldest)?;
+        rotated -= 1;
+
+        log::warn!("dropped the audit log history in {} to stay below max_audit_log_size {} MB", oldest.display(), max_audit_log_size);
+    }
+
+    OpenOptions::new().create(true).append(true).open(path(&audit_dir, entry.tse_id, 0))?.write_all(line.as_bytes())?;
+
+    Ok(())
+}
+
+/// Records every fiscal operation of a TSE in its audit log.
+pub struct Audited {
+    tse_id: String,
+    inner: Backend,
+}
+
+impl Audited {
+    pub fn new(tse_id: String, inner: Backend) -> Audited {
+        Audited { tse_id, inner }
+    }
+
+    fn entry(&self, operation: &'static str) -> Entry<'_> {
+        Entry {
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            tse_id: &self.tse_id,
+            operation,
+            correlation_id: logging::correlation_id(),
+            ..Default::default()
+        }
+    }
+
+    fn record<'a, T>(&self, entry: Entry<'a>, result: Result<T, Error>, complete: impl FnOnce(Entry<'a>, &T) -> Entry<'a>) -> Result<T, Error> {
+        let entry = match &result {
+            Ok(response) => complete(Entry { result: String::from("ok"), ..entry }, response),
+            Err(err) => Entry { result: format!("error: {}", err), ..entry },
+        };
+
+        if let Err(err) = write(&entry) {
+            log::error!("could not write audit log of {}: {}", self.tse_id, err);
+        }
+
+        result
+    }
+}
+
+impl IDeSscd for Audited {
+    type Error = Error;
+
+    fn start_transaction(&self, request: &StartTransactionRequest) -> Result<StartTransactionResponse, Self::Error> {
+        let entry = Entry {
+            client_id: Some(&request.client_id),
+            process_type: Some(&request.process_type),
+            ..self.entry("start_transaction")
+        }
+        .process_data(&request.process_data_base64);
+
+        self.record(entry, self.inner.start_transaction(request), |entry, response| Entry {
+            transaction_number: Some(response.transaction_number),
+            signature_counter: Some(response.signature_data.signature_counter),
+            ..entry
+        })
+    }
+
+    fn update_transaction(&self, request: &UpdateTransactionRequest) -> Result<UpdateTransactionResponse, Self::Error> {
+        let entry = Entry {
+            client_id: Some(&request.client_id),
+            process_type: Some(&request.process_type),
+            transaction_number: Some(request.transaction_number),
+            ..self.entry("update_transaction")
+        }
+        .process_data(&request.process_data_base64);
+
+        self.record(entry, self.inner.update_transaction(request), |entry, response| Entry {
+            signature_counter: Some(response.signature_data.signature_counter),
+            ..entry
+        })
+    }
+
+    fn finish_transaction(&self, request: &FinishTransactionRequest) -> Result<FinishTransactionResponse, Self::Error> {
+        let entry = Entry {
+            client_id: Some(&request.client_id),
+            process_type: Some(&request.process_type),
+            transaction_number: Some(request.transaction_number),
+            ..self.entry("finish_transaction")
+        }
+        .process_data(&request.process_data_base64);
+
+        self.record(entry, self.inner.finish_transaction(request), |entry, response| Entry {
+            signature_counter: Some(response.signature_data.signature_counter),
+            ..entry
+        })
+    }
+
+    fn get_tse_info(&self) -> Result<TseInfo, Self::Error> {
+        self.inner.get_tse_info()
+    }
+
+    fn set_tse_state(&self, state: &TseState) -> Result<TseState, Self::Error> {
+        let entry = Entry {
+            state: Some(format!("{:?}", state.current_state)),
+            ..self.entry("set_tse_state")
+        };
+
+        self.record(entry, self.inner.set_tse_state(state), |entry, _| entry)
+    }
+
+    fn register_client_id(&self, request: &RegisterClientIdRequest) -> Result<RegisterClientIdResponse, Self::Error> {
+        let entry = Entry {
+            client_id: Some(&request.client_id),
+            ..self.entry("register_client_id")
+        };
+
+        self.record(entry, self.inner.register_client_id(request), |entry, _| entry)
+    }
+
+    fn unregister_client_id(&self, request: &UnregisterClientIdRequest) -> Result<UnregisterClientIdResponse, Self::Error> {
+        let entry = Entry {
+            client_id: Some(&request.client_id),
+            ..self.entry("unregister_client_id")
+        };
+
+        self.record(entry, self.inner.unregister_client_id(request), |entry, _| entry)
+    }
+
+    fn execute_set_tse_time(&self) -> Result<(), Self::Error> {
+        self.record(self.entry("execute_set_tse_time"), self.inner.execute_set_tse_time(), |entry, _| entry)
+    }
+
+    fn execute_self_test(&self) -> Result<(), Self::Error> {
+        self.inner.execute_self_test()
+    }
+
+    fn start_export_session(&self, request: &StartExportSessionRequest) -> Result<StartExportSessionResponse, Self::Error> {
+        let entry = Entry {
+            client_id: Some(&request.client_id),
+            ..self.entry("start_export_session")
+        };
+
+        self.record(entry, self.inner.start_export_session(request), |entry, response| Entry { token_id: Some(response.token_id.clone()), ..entry })
+    }
+
+    fn start_export_session_by_time_stamp(&self, request: &StartExportSessionByTimeStampRequest) -> Result<StartExportSessionResponse, Self::Error> {
+        let entry = Entry {
+            client_id: Some(&request.client_id),
+            ..self.entry("start_export_session_by_time_stamp")
+        };
+
+        self.record(entry, self.inner.start_export_session_by_time_stamp(request), |entry, response| Entry { token_id: Some(response.token_id.clone()), ..entry })
+    }
+
+    fn start_export_session_by_transaction(&self, request: &StartExportSessionByTransactionRequest) -> Result<StartExportSessionResponse, Self::Error> {
+        let entry = Entry {
+            client_id: Some(&request.client_id),
+            ..self.entry("start_export_session_by_transaction")
+        };
+
+        self.record(entry, self.inner.start_export_session_by_transaction(request), |entry, response| Entry { token_id: Some(response.token_id.clone()), ..entry })
+    }
+
+    fn export_data(&self, request: &ExportDataRequest) -> Result<ExportDataResponse, Self::Error> {
+        self.inner.export_data(request)
+    }
+
+    fn end_export_session(&self, request: &EndExportSessionRequest) -> Result<EndExportSessionResponse, Self::Error> {
+        let entry = Entry {
+            token_id: Some(request.token_id.clone()),
+            ..self.entry("end_export_session")
+        };
+
+        self.record(entry, self.inner.end_export_session(request), |entry, _| entry)
+    }
+
+    fn echo(&self, request: &ScuDeEchoRequest) -> Result<ScuDeEchoResponse, Self::Error> {
+        self.inner.echo(request)
+    }
+
+    fn get_version(&self) -> Result<ScuDeVersion, Self::Error> {
+        self.inner.get_version()
+    }
+}
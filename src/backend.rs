@@ -0,0 +1,246 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use thiserror::Error;
+
+use crate::{
+    atrustapi::return_codes::ReturnCode,
+    audit::Audited,
+    client,
+    config::{self, Config, TssType},
+    idesscd::*,
+    lifecycle,
+};
+
+/// A transport to a fiskaltrust.SCU (or anything else speaking the `IDeSscd` interface) for a single TSE entry.
+pub type Backend = Arc<dyn IDeSscd<Error = Error> + Send + Sync>;
+
+/// Builds the backend for a TSE entry.
+pub type Factory = fn(&Config) -> Result<Backend, Error>;
+
+/// The backends available for each `tss_type`. New transports are added here without touching the exported functions.
+static BACKENDS: Lazy<HashMap<TssType, Factory>> = Lazy::new(|| {
+    let mut backends: HashMap<TssType, Factory> = HashMap::new();
+
+    backends.insert(TssType::AsignOnline, client::backend);
+    backends.insert(TssType::CryptoVision, client::backend);
+
+    backends
+});
+
+/// Backends already built for a TSE, so the config is only looked up once per TSE.
+static CACHE: Lazy<Mutex<HashMap<String, Backend>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// TSEs whose time the SCU refused to set on the last `execute_set_tse_time`.
+static TIME_NOT_SET: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("at_load has not been called")]
+    NotLoaded,
+
+    #[error("config entry not found for tse: {0}")]
+    NoConfig(String),
+
+    #[error("invalid config: {0}")]
+    InvalidConfig(String),
+
+    #[error("no backend available for tss_type {0:?}")]
+    NoBackend(TssType),
+
+    #[error("Operation not supported by the backend: {0}")]
+    NotSupported(String),
+
+    #[error("Backend rejected the request: {0}")]
+    Unsuccessful(String),
+
+    #[error("Backend returned an invalid response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Communication with the backend failed: {0}")]
+    CommunicationFailed(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("{0}")]
+    Other(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl From<Error> for ReturnCode {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::NotLoaded => ReturnCode::AtLoadNotCalled,
+            Error::NoConfig(_) | Error::InvalidConfig(_) | Error::NoBackend(_) => ReturnCode::InvalidConfig,
+            Error::NotSupported(_) => ReturnCode::FunctionNotSupported,
+            Error::InvalidResponse(_) => ReturnCode::TseResponseDataInvalid,
+            Error::CommunicationFailed(_) => ReturnCode::SeCommunicationFailed,
+            Error::Unsuccessful(_) | Error::Other(_) => ReturnCode::Unknown,
+        }
+    }
+}
+
+/// Returns the backend for the given TSE selected by the `tss_type` of its config entry.
+pub fn get(tse_id: String) -> Result<Backend, Error> {
+    if !lifecycle::is_loaded() {
+        return Err(Error::NotLoaded);
+    }
+
+    if let Some(backend) = CACHE.lock().ok().and_then(|cache| cache.get(&tse_id).cloned()) {
+        return Ok(backend);
+    }
+
+    let config = config::get_tss(&tse_id).ok_or_else(|| Error::NoConfig(tse_id.clone()))?;
+    let factory = BACKENDS.get(&config.tss_type).ok_or_else(|| Error::NoBackend(config.tss_type.clone()))?;
+    let ttl = config::GENERAL_CONFIG.lock().map(|general_config| general_config.tse_info_cache_ttl).unwrap_or(config::DEFAULT_TSE_INFO_CACHE_TTL);
+
+    let backend: Backend = Arc::new(Cached::new(tse_id.clone(), Arc::new(Audited::new(tse_id.clone(), factory(&config)?)), Duration::from_secs(ttl)));
+
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.insert(tse_id, backend.clone());
+    }
+
+    Ok(backend)
+}
+
+/// Drops the cached backend and `TseInfo` of the given TSE, e.g. after its config entry changed.
+pub fn invalidate(tse_id: &str) {
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.remove(tse_id);
+    }
+}
+
+/// Drops all cached backends and `TseInfo`s and closes all circuit breakers as the config they were opened with might have changed.
+pub fn clear() {
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.clear();
+    }
+
+    client::reset_circuit_breakers();
+
+    if let Ok(mut time_not_set) = TIME_NOT_SET.lock() {
+        time_not_set.clear();
+    }
+}
+
+/// Whether the SCU refused the last attempt to set the time of the given TSE.
+pub fn is_time_not_set(tse_id: &str) -> bool {
+    TIME_NOT_SET.lock().map(|time_not_set| time_not_set.contains(tse_id)).unwrap_or(false)
+}
+
+/// Serves `get_tse_info` from memory for `ttl` and forgets it on every call which changes the state of the TSE.
+struct Cached {
+    tse_id: String,
+    inner: Backend,
+    ttl: Duration,
+    tse_info: Mutex<Option<(Instant, TseInfo)>>,
+}
+
+impl Cached {
+    fn new(tse_id: String, inner: Backend, ttl: Duration) -> Cached {
+        Cached { tse_id, inner, ttl, tse_info: Mutex::new(None) }
+    }
+
+    fn invalidate<T>(&self, result: Result<T, Error>) -> Result<T, Error> {
+        if let Ok(mut tse_info) = self.tse_info.lock() {
+            *tse_info = None;
+        }
+
+        result
+    }
+}
+
+impl IDeSscd for Cached {
+    type Error = Error;
+
+    fn start_transaction(&self, request: &StartTransactionRequest) -> Result<StartTransactionResponse, Self::Error> {
+        self.invalidate(self.inner.start_transaction(request))
+    }
+
+    fn update_transaction(&self, request: &UpdateTransactionRequest) -> Result<UpdateTransactionResponse, Self::Error> {
+        self.invalidate(self.inner.update_transaction(request))
+    }
+
+    fn finish_transaction(&self, request: &FinishTransactionRequest) -> Result<FinishTransactionResponse, Self::Error> {
+        self.invalidate(self.inner.finish_transaction(request))
+    }
+
+    fn get_tse_info(&self) -> Result<TseInfo, Self::Error> {
+        if self.ttl.is_zero() {
+            return self.inner.get_tse_info();
+        }
+
+        let mut cached = self.tse_info.lock().map_err(|err| Error::Other(err.to_string().into()))?;
+
+        if let Some((fetched, tse_info)) = &*cached {
+            if fetched.elapsed() < self.ttl {
+                log::trace!("tseinfo served from cache");
+                return Ok(tse_info.clone());
+            }
+        }
+
+        let tse_info = self.inner.get_tse_info()?;
+        *cached = Some((Instant::now(), tse_info.clone()));
+
+        Ok(tse_info)
+    }
+
+    fn set_tse_state(&self, state: &TseState) -> Result<TseState, Self::Error> {
+        self.invalidate(self.inner.set_tse_state(state))
+    }
+
+    fn register_client_id(&self, request: &RegisterClientIdRequest) -> Result<RegisterClientIdResponse, Self::Error> {
+        self.invalidate(self.inner.register_client_id(request))
+    }
+
+    fn unregister_client_id(&self, request: &UnregisterClientIdRequest) -> Result<UnregisterClientIdResponse, Self::Error> {
+        self.invalidate(self.inner.unregister_client_id(request))
+    }
+
+    fn execute_set_tse_time(&self) -> Result<(), Self::Error> {
+        let result = self.invalidate(self.inner.execute_set_tse_time());
+
+        if let Ok(mut time_not_set) = TIME_NOT_SET.lock() {
+            match result {
+                Ok(()) => time_not_set.remove(&self.tse_id),
+                Err(Error::Unsuccessful(_)) => time_not_set.insert(self.tse_id.clone()),
+                Err(_) => false,
+            };
+        }
+
+        result
+    }
+
+    fn execute_self_test(&self) -> Result<(), Self::Error> {
+        self.invalidate(self.inner.execute_self_test())
+    }
+
+    fn start_export_session(&self, request: &StartExportSessionRequest) -> Result<StartExportSessionResponse, Self::Error> {
+        self.inner.start_export_session(request)
+    }
+
+    fn start_export_session_by_time_stamp(&self, request: &StartExportSessionByTimeStampRequest) -> Result<StartExportSessionResponse, Self::Error> {
+        self.inner.start_export_session_by_time_stamp(request)
+    }
+
+    fn start_export_session_by_transaction(&self, request: &StartExportSessionByTransactionRequest) -> Result<StartExportSessionResponse, Self::Error> {
+        self.inner.start_export_session_by_transaction(request)
+    }
+
+    fn export_data(&self, request: &ExportDataRequest) -> Result<ExportDataResponse, Self::Error> {
+        self.inner.export_data(request)
+    }
+
+    fn end_export_session(&self, request: &EndExportSessionRequest) -> Result<EndExportSessionResponse, Self::Error> {
+        self.invalidate(self.inner.end_export_session(request))
+    }
+
+    fn echo(&self, request: &ScuDeEchoRequest) -> Result<ScuDeEchoResponse, Self::Error> {
+        self.inner.echo(request)
+    }
+
+    fn get_version(&self) -> Result<ScuDeVersion, Self::Error> {
+        self.inner.get_version()
+    }
+}
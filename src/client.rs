@@ -1,61 +1,469 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use arc_swap::ArcSwap;
-use log::{error, warn};
+use log::{debug, error, info, warn};
 use once_cell::sync::Lazy;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
 use thiserror::Error;
 
-use crate::{atrustapi::return_codes::ReturnCode, config, idesscd::*};
+use crate::{
+    backend::{self, Backend},
+    config::{self, Config, Secret},
+    idesscd::*,
+    lifecycle, logging,
+};
 
-fn set_client() -> reqwest::blocking::Client {
+fn read_tls_file(key: &str, path: &str) -> Result<Vec<u8>, Error> {
+    std::fs::read(path).map_err(|err| Error::InvalidHttpConfig(format!("{} {} could not be read: {}", key, path, err)))
+}
+
+fn proxy(http_proxy: &str, username: Option<&str>, password: Option<&str>) -> Result<reqwest::Proxy, Box<dyn std::error::Error>> {
     #[derive(Debug, thiserror::Error)]
     enum Error {
         #[error("Unknown proxy scheme configured: {0}")]
         UnknownProxyScheme(String),
     }
 
-    try_or_return! {
-        || -> Result<reqwest::blocking::Client, Box<dyn std::error::Error>> {
-            let mut builder = reqwest::blocking::Client::builder();
+    let url = reqwest::Url::parse(http_proxy).map_err(Box::new)?;
 
-            let general_config = &*config::GENERAL_CONFIG.lock().map_err(Box::new)?;
+    let proxy = match url.scheme() {
+        "http" => reqwest::Proxy::http(http_proxy).map_err(Box::new)?,
+        "https" => reqwest::Proxy::https(http_proxy).map_err(Box::new)?,
+        scheme => return Err(Box::new(Error::UnknownProxyScheme(scheme.to_string()))),
+    };
 
-            if let Some(http_proxy) = &general_config.http_proxy {
-                let url = reqwest::Url::parse(http_proxy).map_err(Box::new)?;
+    Ok(match username {
+        Some(username) => proxy.basic_auth(username, password.unwrap_or("")),
+        None => proxy,
+    })
+}
 
-                builder = match url.scheme() {
-                    "http" => builder.proxy(reqwest::Proxy::http(http_proxy).map_err(Box::new)?),
-                    "https" => builder.proxy(reqwest::Proxy::https(http_proxy).map_err(Box::new)?),
-                    scheme => { return Err(Box::new(Error::UnknownProxyScheme(scheme.to_string()))) }
-                };
-            } else {
-                return Ok(reqwest::blocking::Client::new());
-            }
+/// Starts a http client with the given timeout in milliseconds and proxy.
+fn client_builder(timeout: u64, http_proxy: Option<&String>, username: Option<&str>, password: Option<&str>) -> reqwest::blocking::ClientBuilder {
+    let builder = reqwest::blocking::Client::builder().timeout(Duration::from_millis(timeout));
 
-            Ok(builder.build().map_err(Box::new)?)
-        },
-        |err| {
+    match http_proxy.map(|http_proxy| proxy(http_proxy, username, password)) {
+        Some(Ok(proxy)) => builder.proxy(proxy),
+        Some(Err(err)) => {
             error!("{}", err);
             warn!("No proxy used");
-            reqwest::blocking::Client::new()
+            builder
         }
+        None => builder,
     }
 }
 
-static CLIENT: Lazy<ArcSwap<reqwest::blocking::Client>> = Lazy::new(|| ArcSwap::new(Arc::new(set_client())));
+/// Builds a http client from the settings of the `config` section for requests not sent to a TSE, like the heartbeat.
+pub(crate) fn general_http_client() -> Result<reqwest::blocking::Client, Error> {
+    let general_config = config::GENERAL_CONFIG.lock().map_err(|err| Error::InvalidHttpConfig(err.to_string()))?.clone();
+
+    client_builder(general_config.timeout, general_config.http_proxy.as_ref(), general_config.http_proxy_username.as_deref(), general_config.http_proxy_password.as_ref().map(Secret::expose))
+        .build()
+        .map_err(|err| Error::InvalidHttpConfig(err.to_string()))
+}
+
+/// Builds the http client of a TSE from the settings of its config entry, falling back to those of the `config` section.
+fn http_client(config: &Config) -> Result<reqwest::blocking::Client, Error> {
+    let general_config = config::GENERAL_CONFIG.lock().map_err(|err| Error::InvalidHttpConfig(err.to_string()))?.clone();
+    let (http, tls) = (&config.http, &config.tls);
+
+    let (http_proxy, username, password) = match &http.http_proxy {
+        Some(http_proxy) => (Some(http_proxy), &http.http_proxy_username, &http.http_proxy_password),
+        None => (general_config.http_proxy.as_ref(), &general_config.http_proxy_username, &general_config.http_proxy_password),
+    };
+
+    let mut builder = client_builder(http.timeout.unwrap_or(general_config.timeout), http_proxy, username.as_deref(), password.as_ref().map(Secret::expose));
+
+    if !tls.pin_sha256.is_empty() {
+        builder = builder.use_preconfigured_tls(pinned_tls_config(tls)?);
+    } else {
+        if let Some(ca_file) = &tls.ca_file {
+            let bundle = read_tls_file("tls_ca_file", ca_file)?;
+
+            for pem in x509_parser::pem::Pem::iter_from_buffer(&bundle) {
+                let pem = pem.map_err(|err| Error::InvalidHttpConfig(format!("tls_ca_file {} is not a PEM file: {}", ca_file, err)))?;
+                let certificate = reqwest::Certificate::from_der(&pem.contents).map_err(|err| Error::InvalidHttpConfig(format!("tls_ca_file {} contains an invalid certificate: {}", ca_file, err)))?;
+
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(client_cert) = &tls.client_cert {
+            let cert = read_tls_file("tls_client_cert", client_cert)?;
+
+            let identity = match &tls.client_key {
+                Some(client_key) => reqwest::Identity::from_pkcs8_pem(&cert, &read_tls_file("tls_client_key", client_key)?),
+                None => reqwest::Identity::from_pkcs12_der(&cert, tls.client_cert_password.as_ref().map(Secret::expose).unwrap_or("")),
+            }
+            .map_err(|err| Error::InvalidHttpConfig(format!("tls_client_cert {} could not be loaded: {}", client_cert, err)))?;
+
+            builder = builder.identity(identity);
+        }
+
+        if tls.accept_invalid_certs {
+            warn!("tls_accept_invalid_certs is set, the certificate of the scu is not verified");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+    }
+
+    builder.build().map_err(|err| Error::InvalidHttpConfig(err.to_string()))
+}
+
+/// Verifies the SCU certificate during the handshake and accepts it only if its SHA-256 fingerprint matches one of `tls_pin_sha256`,
+/// so nothing is sent to a server with a wrong certificate.
+struct PinnedVerifier {
+    webpki: Option<rustls::client::WebPkiVerifier>,
+    pins: Vec<String>,
+}
+
+impl rustls::client::ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(&self, end_entity: &rustls::Certificate, intermediates: &[rustls::Certificate], server_name: &rustls::ServerName, scts: &mut dyn Iterator<Item = &[u8]>, ocsp_response: &[u8], now: std::time::SystemTime) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        if let Some(webpki) = &self.webpki {
+            webpki.verify_server_cert(end_entity, intermediates, server_name, scts, ocsp_response, now)?;
+        }
+
+        match self.pins.contains(&sha256::digest(end_entity.0.as_slice())) {
+            true => Ok(rustls::client::ServerCertVerified::assertion()),
+            false => Err(rustls::Error::General(String::from("certificate does not match any of tls_pin_sha256"))),
+        }
+    }
+}
+
+/// Builds the TLS config of a TSE with pinned certificates. Only PEM client certificates can be used together with pins.
+fn pinned_tls_config(tls: &config::TlsConfig) -> Result<rustls::ClientConfig, Error> {
+    let webpki = match tls.accept_invalid_certs {
+        true => {
+            warn!("tls_accept_invalid_certs is set, the certificate of the scu is only checked against tls_pin_sha256");
+            None
+        }
+        false => {
+            let mut roots = rustls::RootCertStore::empty();
+
+            match &tls.ca_file {
+                Some(ca_file) => {
+                    let bundle = read_tls_file("tls_ca_file", ca_file)?;
+                    let certificates = rustls_pemfile::certs(&mut bundle.as_slice()).map_err(|err| Error::InvalidHttpConfig(format!("tls_ca_file {} is not a PEM file: {}", ca_file, err)))?;
+
+                    for certificate in certificates {
+                        roots.add(&rustls::Certificate(certificate)).map_err(|err| Error::InvalidHttpConfig(format!("tls_ca_file {} contains an invalid certificate: {}", ca_file, err)))?;
+                    }
+                }
+                None => {
+                    let certificates = rustls_native_certs::load_native_certs().map_err(|err| Error::InvalidHttpConfig(format!("native root certificates could not be loaded: {}", err)))?;
+                    roots.add_parsable_certificates(&certificates.into_iter().map(|certificate| certificate.0).collect::<Vec<_>>());
+                }
+            }
+
+            Some(rustls::client::WebPkiVerifier::new(roots, None))
+        }
+    };
+
+    let verifier = PinnedVerifier {
+        webpki,
+        pins: tls.pin_sha256.iter().map(|pin| pin.replace(':', "").to_lowercase()).collect(),
+    };
+    let builder = rustls::ClientConfig::builder().with_safe_defaults().with_custom_certificate_verifier(Arc::new(verifier));
+
+    match (&tls.client_cert, &tls.client_key) {
+        (None, _) => Ok(builder.with_no_client_auth()),
+        (Some(_), None) => Err(Error::InvalidHttpConfig(String::from("tls_pin_sha256 requires a PEM tls_client_cert with tls_client_key"))),
+        (Some(client_cert), Some(client_key)) => {
+            let invalid = |err: &dyn std::fmt::Display| Error::InvalidHttpConfig(format!("tls_client_cert {} could not be loaded: {}", client_cert, err));
+
+            let certs = rustls_pemfile::certs(&mut read_tls_file("tls_client_cert", client_cert)?.as_slice()).map_err(|err| invalid(&err))?;
+            let key = rustls_pemfile::read_all(&mut read_tls_file("tls_client_key", client_key)?.as_slice())
+                .map_err(|err| invalid(&err))?
+                .into_iter()
+                .find_map(|item| match item {
+                    rustls_pemfile::Item::PKCS8Key(key) | rustls_pemfile::Item::RSAKey(key) | rustls_pemfile::Item::ECKey(key) => Some(key),
+                    _ => None,
+                })
+                .ok_or_else(|| invalid(&"tls_client_key contains no private key"))?;
+
+            builder.with_client_auth_cert(certs.into_iter().map(rustls::Certificate).collect(), rustls::PrivateKey(key)).map_err(|err| invalid(&err))
+        }
+    }
+}
+
+/// Builds the credentials and additional headers sent with every request of a TSE.
+fn headers(config: &Config) -> Result<HeaderMap, Error> {
+    let auth = &config.auth;
+    let mut headers = HeaderMap::new();
+
+    let required = |value: Option<&str>, fallback: Option<&str>, keys: &str| value.or(fallback).map(String::from).ok_or_else(|| Error::InvalidHttpConfig(format!("auth_type {} requires {}", auth.auth_type.as_deref().unwrap_or_default(), keys)));
+    let header_value = |value: String| {
+        let mut value = HeaderValue::from_str(&value).map_err(|err| Error::InvalidHttpConfig(format!("invalid auth header value: {}", err)))?;
+        value.set_sensitive(true);
+        Ok::<HeaderValue, Error>(value)
+    };
+
+    match auth.auth_type.as_deref() {
+        None | Some("none") => {}
+        Some("api_key") => {
+            let name = auth.header.as_deref().unwrap_or("X-Api-Key");
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|err| Error::InvalidHttpConfig(format!("invalid auth_header {}: {}", name, err)))?;
+
+            headers.insert(name, header_value(required(auth.api_key.as_ref().map(Secret::expose), config.atrust_api_key.as_ref().map(Secret::expose), "auth_api_key or atrust_api_key")?)?);
+        }
+        Some("bearer") => {
+            let token = required(auth.token.as_ref().map(Secret::expose), config.atrust_api_key.as_ref().map(Secret::expose), "auth_token or atrust_api_key")?;
+
+            headers.insert(AUTHORIZATION, header_value(format!("Bearer {}", token))?);
+        }
+        Some("basic") => {
+            let username = required(auth.username.as_deref(), config.atrust_vtss_id.as_deref(), "auth_username or atrust_vtss_id")?;
+            let password = required(auth.password.as_ref().map(Secret::expose), config.atrust_api_key.as_ref().map(Secret::expose), "auth_password or atrust_api_key")?;
+
+            headers.insert(AUTHORIZATION, header_value(format!("Basic {}", base64::encode(format!("{}:{}", username, password))))?);
+        }
+        Some(auth_type) => return Err(Error::InvalidHttpConfig(format!("unknown auth_type {}", auth_type))),
+    }
+
+    for (name, value) in &auth.headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|err| Error::InvalidHttpConfig(format!("invalid header name http_header_{}: {}", name, err)))?;
+        let header_value = HeaderValue::from_str(value).map_err(|err| Error::InvalidHttpConfig(format!("invalid value of http_header_{}: {}", name, err)))?;
+
+        headers.insert(header_name, header_value);
+    }
+
+    Ok(headers)
+}
+
+/// The http client of a TSE.
+struct Http {
+    client: reqwest::blocking::Client,
+    headers: HeaderMap,
+    retries: u64,
+}
+
+impl Http {
+    fn new(config: &Config) -> Result<Http, Error> {
+        let retries = match config.http.retries {
+            Some(retries) => retries,
+            None => config::GENERAL_CONFIG.lock().map(|general_config| general_config.retries).unwrap_or(config::DEFAULT_NUMBER_OF_RETRIES),
+        };
+
+        Ok(Http {
+            client: http_client(config)?,
+            headers: headers(config)?,
+            retries,
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, url: String) -> reqwest::blocking::RequestBuilder {
+        let request = self.client.request(method, url).headers(self.headers.clone());
+
+        match logging::correlation_id() {
+            Some(correlation_id) => request.header(CORRELATION_ID_HEADER, correlation_id),
+            None => request,
+        }
+    }
+
+    /// Sends the request, retrying up to `retries` times if no connection could be established.
+    fn execute(&self, mut request: reqwest::blocking::Request) -> Result<reqwest::blocking::Response, Error> {
+        let (method, url) = (request.method().clone(), request.url().clone());
+        let request_size = request.body().and_then(|body| body.as_bytes()).map(<[u8]>::len).unwrap_or(0);
+        let started = Instant::now();
+
+        let mut attempt = 0;
+        let response = loop {
+            let retry = if attempt < self.retries { request.try_clone() } else { None };
+
+            match (self.client.execute(request), retry) {
+                (Err(source), Some(retry)) if source.is_connect() => {
+                    attempt += 1;
+                    warn!("{}, retrying ({}/{})", source, attempt, self.retries);
+                    request = retry;
+                }
+                (response, _) => {
+                    break response.map_err(|source| {
+                        debug!("{} {} failed after {:?}, sent {} bytes", method, url, started.elapsed(), request_size);
+
+                        match source.is_connect() || method == reqwest::Method::GET {
+                            true => Error::Unreachable { source },
+                            false => Error::RequestFailed { source },
+                        }
+                    })?
+                }
+            }
+        };
+
+        debug!("{} {} returned {} after {:?}, sent {} bytes, received {} bytes", method, url, response.status(), started.elapsed(), request_size, response.content_length().unwrap_or(0));
+
+        Ok(response)
+    }
+}
 
 const URL_VERSION: &str = "v1";
+
+/// Url versions of the SCU api this wrapper can speak, newest first. Only `v1` exists so far, negotiating it checks that the SCU serves it.
+const SUPPORTED_URL_VERSIONS: [&str; 1] = ["v1"];
+
+/// Endpoints of the SCU api, each one is probed as not every SCU version serves all of them.
+const ENDPOINTS: [&str; 16] = [
+    "starttransaction",
+    "updatetransaction",
+    "finishtransaction",
+    "tseinfo",
+    "tsestate",
+    "registerclientid",
+    "unregisterclientid",
+    "executesettsetime",
+    "executeselftest",
+    "startexportsession",
+    "startexportsessionbytimestamp",
+    "startexportsessionbytransaction",
+    "exportdata",
+    "endexportsession",
+    "echo",
+    "version",
+];
+
+static NEGOTIATED_URL_VERSIONS: Lazy<Mutex<HashMap<String, &'static str>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// SCUs which could not be reached, with the time they failed. They are skipped until `scu_failover_cooldown` has passed.
+static UNREACHABLE_SCUS: Lazy<Mutex<HashMap<String, Instant>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn is_unreachable(base_url: &str) -> bool {
+    let cooldown = config::GENERAL_CONFIG.lock().map(|general_config| general_config.scu_failover_cooldown).unwrap_or(config::DEFAULT_SCU_FAILOVER_COOLDOWN);
+
+    UNREACHABLE_SCUS.lock().ok().and_then(|unreachable| unreachable.get(base_url).map(|since| since.elapsed() < Duration::from_secs(cooldown))).unwrap_or(false)
+}
+
+fn set_reachable(base_url: &str, reachable: bool) {
+    if let Ok(mut unreachable) = UNREACHABLE_SCUS.lock() {
+        if reachable {
+            if unreachable.remove(base_url).is_some() {
+                info!("scu {} is reachable again", base_url);
+            }
+        } else {
+            unreachable.insert(base_url.to_string(), Instant::now());
+        }
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitBreakerState {
+    /// Requests are sent to the SCU.
+    Closed = 0,
+    /// The SCU failed repeatedly, requests fail immediately without contacting it.
+    Open = 1,
+    /// The reset timeout passed, the next request first probes the SCU with an echo and is the only one sent until it finished.
+    HalfOpen = 2,
+}
+
+struct CircuitBreaker {
+    state: CircuitBreakerState,
+    failures: u32,
+    opened: Instant,
+}
+
+static CIRCUIT_BREAKERS: Lazy<Mutex<HashMap<String, CircuitBreaker>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn circuit_breaker_config() -> (u32, Duration) {
+    config::GENERAL_CONFIG
+        .lock()
+        .map(|general_config| (general_config.circuit_breaker_threshold, Duration::from_secs(general_config.circuit_breaker_reset)))
+        .unwrap_or((config::DEFAULT_CIRCUIT_BREAKER_THRESHOLD, Duration::from_secs(config::DEFAULT_CIRCUIT_BREAKER_RESET)))
+}
+
+/// Returns the circuit breaker state of the given TSE. An open circuit breaker whose reset timeout passed is reported as half open.
+pub fn circuit_breaker_state(tse_id: &str) -> CircuitBreakerState {
+    let (_, reset) = circuit_breaker_config();
+
+    match CIRCUIT_BREAKERS.lock().ok().and_then(|breakers| breakers.get(tse_id).map(|breaker| (breaker.state, breaker.opened))) {
+        Some((CircuitBreakerState::Open, opened)) if opened.elapsed() >= reset => CircuitBreakerState::HalfOpen,
+        Some((state, _)) => state,
+        None => CircuitBreakerState::Closed,
+    }
+}
+
+/// Forgets the negotiated api versions, unreachable SCUs and circuit breakers of all TSEs.
+pub fn reset() {
+    if let Ok(mut versions) = NEGOTIATED_URL_VERSIONS.lock() {
+        versions.clear();
+    }
+
+    if let Ok(mut unreachable) = UNREACHABLE_SCUS.lock() {
+        unreachable.clear();
+    }
+
+    reset_circuit_breakers();
+}
+
+/// Closes the circuit breakers of all TSEs, e.g. after the config changed.
+pub fn reset_circuit_breakers() {
+    if let Ok(mut breakers) = CIRCUIT_BREAKERS.lock() {
+        breakers.clear();
+    }
+}
+
+fn record_circuit_breaker(tse_id: &str, success: bool) {
+    let (threshold, _) = circuit_breaker_config();
+    let mut breakers = ok_or_return!(CIRCUIT_BREAKERS.lock(), |_| ());
+
+    // the threshold might have been set to 0 while the circuit breaker was open
+    if threshold == 0 {
+        breakers.remove(tse_id);
+        return;
+    }
+
+    let breaker = breakers.entry(tse_id.to_string()).or_insert(CircuitBreaker {
+        state: CircuitBreakerState::Closed,
+        failures: 0,
+        opened: Instant::now(),
+    });
+
+    if success {
+        if breaker.state != CircuitBreakerState::Closed {
+            info!("circuit breaker for tse {} closed", tse_id);
+        }
+
+        breaker.state = CircuitBreakerState::Closed;
+        breaker.failures = 0;
+        return;
+    }
+
+    breaker.failures += 1;
+    if breaker.state == CircuitBreakerState::HalfOpen || breaker.failures >= threshold {
+        if breaker.state != CircuitBreakerState::Open {
+            warn!("circuit breaker for tse {} opened after {} failed requests", tse_id, breaker.failures);
+        }
+
+        breaker.state = CircuitBreakerState::Open;
+        breaker.opened = Instant::now();
+    }
+}
+
 macro_rules! url_version {
-    ($base_url:expr, $path:expr) => {
-        format!("{}/{}/{}", $base_url, URL_VERSION, $path)
+    ($scu:expr, $path:expr) => {
+        format!("{}/{}/{}", $scu.base_url, $scu.url_version, $path)
     };
 }
 
 #[derive(Error, Debug)]
 pub enum Error {
+    /// The request may have reached the SCU, so it must not be sent to another one.
     #[error("Request could not be sent: {source}")]
     RequestFailed {
-        #[from]
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// No connection could be established or a `GET` request got no response, so it is safe to send it to another SCU.
+    #[error("SCU could not be reached: {source}")]
+    Unreachable {
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("Response could not be decoded: {source}")]
+    DecodingFailed {
+        #[source]
         source: reqwest::Error,
     },
 
@@ -70,27 +478,71 @@ pub enum Error {
 
     #[error("scu_url not found for tse: {0}")]
     NoScuUrl(String),
+
+    #[error("scu does not support any of the url versions {:?}: {0}", SUPPORTED_URL_VERSIONS)]
+    NoSupportedUrlVersion(String),
+
+    #[error("circuit breaker for tse {0} is open, the scu is not contacted")]
+    CircuitBreakerOpen(String),
+
+    #[error("invalid http config: {0}")]
+    InvalidHttpConfig(String),
+
+    #[error("shared state is unusable after a panic: {0}")]
+    Poisoned(String),
 }
 
-impl From<Error> for ReturnCode {
+impl Error {
+    /// Whether the error counts as a failure of the SCU for the circuit breaker: it could not be reached or it answered with a server error.
+    /// Rejected requests like `4xx` responses are the fault of the caller and do not count.
+    fn trips_circuit_breaker(&self) -> bool {
+        match self {
+            Error::RequestFailed { .. } | Error::Unreachable { .. } => true,
+            Error::Unsuccessful(status) => status.is_server_error(),
+            _ => false,
+        }
+    }
+}
+
+impl From<Error> for backend::Error {
     fn from(err: Error) -> Self {
         match err {
-            Error::RequestFailed { source: _ } => ReturnCode::SeCommunicationFailed,
-            Error::NoScuUrl(_) => ReturnCode::InvalidConfig,
-            _ => ReturnCode::Unknown,
+            Error::RequestFailed { .. } | Error::Unreachable { .. } | Error::CircuitBreakerOpen(_) => backend::Error::CommunicationFailed(Box::new(err)),
+            Error::DecodingFailed { .. } => backend::Error::InvalidResponse(err.to_string()),
+            Error::InvalidHttpConfig(_) => backend::Error::InvalidConfig(err.to_string()),
+            Error::Unsuccessful(reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::METHOD_NOT_ALLOWED) | Error::NoSupportedUrlVersion(_) => backend::Error::NotSupported(err.to_string()),
+            Error::Unsuccessful(_) => backend::Error::Unsuccessful(err.to_string()),
+            Error::NoScuUrl(tse_id) => backend::Error::NoConfig(tse_id),
+            Error::BuildingRequestFailed { .. } | Error::Poisoned(_) => backend::Error::Other(Box::new(err)),
         }
     }
 }
 
+pub(crate) const CORRELATION_ID_HEADER: &str = "X-Correlation-Id";
+
+/// Whether request and response bodies, which contain process data, are logged.
+fn log_bodies() -> bool {
+    config::GENERAL_CONFIG.lock().map(|general_config| general_config.log_bodies).unwrap_or(false)
+}
+
+/// The body as it is sent or received, unlike its `Debug` output this includes the process data.
+fn body<T: serde::Serialize>(body: &T) -> String {
+    serde_json::to_string(body).unwrap_or_else(|err| err.to_string())
+}
+
 macro_rules! process_response {
     ($response:expr) => {{
-        if !$response.status().is_success() {
-            return Err(Error::Unsuccessful($response.status()).into());
+        let response = $response;
+
+        if !response.status().is_success() {
+            return Err(Error::Unsuccessful(response.status()).into());
         }
 
-        let response = $response.json()?;
+        let response = response.json().map_err(|source| Error::DecodingFailed { source })?;
 
-        log::trace!("{:?}", response);
+        if log_bodies() {
+            log::trace!("response body: {}", body(&response));
+        }
 
         Ok(response)
     }};
@@ -98,8 +550,10 @@ macro_rules! process_response {
 
 macro_rules! process_empty_response {
     ($response:expr) => {{
-        if !$response.status().is_success() {
-            return Err(Error::Unsuccessful($response.status()).into());
+        let response = $response;
+
+        if !response.status().is_success() {
+            return Err(Error::Unsuccessful(response.status()).into());
         }
 
         Ok(())
@@ -107,109 +561,260 @@ macro_rules! process_empty_response {
 }
 
 macro_rules! post {
-    ($url:expr, $body:expr) => {{
-        let client = CLIENT.load();
-        let request = client.post($url).json($body).build().map_err(|source| Error::BuildingRequestFailed { source })?;
+    ($http:expr, $url:expr, $body:expr) => {{
+        let request = $http.request(reqwest::Method::POST, $url).json($body).build().map_err(|source| Error::BuildingRequestFailed { source })?;
 
-        log::trace!("{:?}", $body);
-        log::trace!("{:?}", request);
+        if log_bodies() {
+            log::trace!("request body: {}", body($body));
+        }
 
-        client.execute(request).map_err(|source| Error::RequestFailed { source })?
+        $http.execute(request)?
     }};
 
-    ($url:expr) => {{
-        let client = CLIENT.load();
-        let request = client.post($url).header(reqwest::header::CONTENT_LENGTH, 0).build().map_err(|source| Error::BuildingRequestFailed { source })?;
-
-        log::trace!("{:?}", request);
+    ($http:expr, $url:expr) => {{
+        let request = $http.request(reqwest::Method::POST, $url).header(reqwest::header::CONTENT_LENGTH, 0).build().map_err(|source| Error::BuildingRequestFailed { source })?;
 
-        client.execute(request).map_err(|source| Error::RequestFailed { source })?
+        $http.execute(request)?
     }};
 }
 
 macro_rules! get {
-    ($url:expr) => {{
-        let client = CLIENT.load();
-        let request = client.get($url).build().map_err(|source| Error::BuildingRequestFailed { source })?;
+    ($http:expr, $url:expr) => {{
+        let request = $http.request(reqwest::Method::GET, $url).build().map_err(|source| Error::BuildingRequestFailed { source })?;
 
-        log::trace!("{:?}", request);
-
-        client.execute(request).map_err(|source| Error::RequestFailed { source })?
+        $http.execute(request)?
     }};
 }
 
-pub struct Client {
+struct Scu {
     base_url: String,
+    url_version: &'static str,
+}
+
+impl Scu {
+    fn new(base_url: String) -> Scu {
+        let url_version = NEGOTIATED_URL_VERSIONS.lock().ok().and_then(|n| n.get(&base_url).copied()).unwrap_or(URL_VERSION);
+
+        Scu { base_url, url_version }
+    }
+
+    /// Checks whether the SCU serves the endpoint with a `GET` request, which has no side effects and is answered with `405 Method Not Allowed` by endpoints only accepting `POST`.
+    fn probe(&self, http: &Http, path: &str) -> Result<bool, Error> {
+        let response = get!(http, url_version!(self, path));
+
+        match response.status() {
+            status if status.is_success() => Ok(true),
+            reqwest::StatusCode::METHOD_NOT_ALLOWED => Ok(true),
+            reqwest::StatusCode::NOT_FOUND => Ok(false),
+            status => Err(Error::Unsuccessful(status)),
+        }
+    }
+}
+
+pub struct Client {
+    tse_id: String,
+    scus: Vec<Scu>,
+    http: Http,
 }
 
 impl Client {
-    pub fn get(tse_id: String) -> Result<Client, Error> {
+    pub fn new(config: &Config) -> Result<Client, Error> {
         Ok(Client {
-            base_url: config::get_tss(&tse_id).ok_or(Error::NoScuUrl(tse_id))?.scu_url,
+            tse_id: config.name.clone(),
+            scus: config.scu_urls().into_iter().map(Scu::new).collect(),
+            http: Http::new(config)?,
         })
     }
+
+    /// Sends the request unless the circuit breaker of the TSE is open. Once the reset timeout passed, the circuit breaker is half open and a single trial request is sent
+    /// after probing the SCU with an echo, all other requests fail immediately until the trial closed or reopened the circuit breaker.
+    fn failover<T>(&self, request: impl Fn(&Scu) -> Result<T, Error>) -> Result<T, backend::Error> {
+        let _correlation = logging::Correlation::start();
+        let (_, reset) = circuit_breaker_config();
+
+        let trial = match CIRCUIT_BREAKERS.lock().as_deref_mut().ok().and_then(|breakers| breakers.get_mut(&self.tse_id)) {
+            Some(breaker) if breaker.state == CircuitBreakerState::Open && breaker.opened.elapsed() < reset => return Err(Error::CircuitBreakerOpen(self.tse_id.clone()).into()),
+            Some(breaker) if breaker.state == CircuitBreakerState::HalfOpen => return Err(Error::CircuitBreakerOpen(self.tse_id.clone()).into()),
+            Some(breaker) if breaker.state == CircuitBreakerState::Open => {
+                breaker.state = CircuitBreakerState::HalfOpen;
+                true
+            }
+            _ => false,
+        };
+
+        if trial {
+            info!("circuit breaker for tse {} is half open, probing the scu", self.tse_id);
+
+            if let Err(err) = self.send(|scu| process_response!(post!(self.http, url_version!(scu, "echo"), &ScuDeEchoRequest { message: self.tse_id.clone() })).map(|_: ScuDeEchoResponse| ())) {
+                record_circuit_breaker(&self.tse_id, false);
+                return Err(err.into());
+            }
+        }
+
+        let result = self.send(request);
+        record_circuit_breaker(&self.tse_id, !matches!(&result, Err(err) if err.trips_circuit_breaker()));
+
+        result.map_err(Into::into)
+    }
+
+    /// Sends the request to the first reachable SCU in the configured order. SCUs which failed recently are only tried if all others fail as well.
+    /// Only requests which could not have been processed are sent to the next SCU, so a transaction is never signed twice.
+    fn send<T>(&self, request: impl Fn(&Scu) -> Result<T, Error>) -> Result<T, Error> {
+        let (reachable, unreachable): (Vec<&Scu>, Vec<&Scu>) = self.scus.iter().partition(|scu| !is_unreachable(&scu.base_url));
+
+        let mut last_err = Error::NoScuUrl(self.tse_id.clone());
+        for scu in reachable.into_iter().chain(unreachable) {
+            match request(scu) {
+                Err(err @ Error::Unreachable { .. }) => {
+                    warn!("scu {} failed: {}", scu.base_url, err);
+                    set_reachable(&scu.base_url, false);
+                    last_err = err;
+                }
+                Err(err @ Error::RequestFailed { .. }) => {
+                    warn!("scu {} failed after the request was sent, not trying another scu: {}", scu.base_url, err);
+                    return Err(err);
+                }
+                result => {
+                    set_reachable(&scu.base_url, true);
+                    return result;
+                }
+            }
+        }
+
+        Err(last_err)
+    }
+}
+
+pub struct Compatibility {
+    pub url_version: &'static str,
+    pub endpoints: Vec<&'static str>,
+}
+
+/// Finds the newest url version each SCU of the given TSE supports, which is then used by all clients for that SCU, and probes which endpoints it serves.
+/// Returns the compatibility of the first reachable SCU.
+pub fn negotiate(tse_id: String) -> Result<Compatibility, backend::Error> {
+    if !lifecycle::is_loaded() {
+        return Err(backend::Error::NotLoaded);
+    }
+
+    let config = config::get_tss(&tse_id).ok_or_else(|| Error::NoScuUrl(tse_id.clone()))?;
+
+    let mut last_err = Error::NoScuUrl(tse_id.clone());
+    let mut compatibility = None;
+    let http = Http::new(&config)?;
+
+    for base_url in config.scu_urls() {
+        match negotiate_scu(&http, &tse_id, base_url) {
+            Ok(negotiated) => {
+                compatibility.get_or_insert(negotiated);
+            }
+            Err(err) => {
+                warn!("{}", err);
+                last_err = err;
+            }
+        }
+    }
+
+    backend::invalidate(&tse_id);
+
+    compatibility.ok_or_else(|| last_err.into())
+}
+
+fn negotiate_scu(http: &Http, tse_id: &str, base_url: String) -> Result<Compatibility, Error> {
+    for url_version in SUPPORTED_URL_VERSIONS {
+        let scu = Scu { base_url: base_url.clone(), url_version };
+
+        let response = post!(http, url_version!(scu, "echo"), &ScuDeEchoRequest { message: tse_id.to_string() });
+        match response.status() {
+            status if status.is_success() => {}
+            reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::METHOD_NOT_ALLOWED => continue,
+            status => return Err(Error::Unsuccessful(status)),
+        }
+
+        let mut endpoints = vec![];
+        for endpoint in ENDPOINTS {
+            match scu.probe(http, endpoint)? {
+                true => endpoints.push(endpoint),
+                false => warn!("scu {} does not serve the {} endpoint", base_url, endpoint),
+            }
+        }
+
+        NEGOTIATED_URL_VERSIONS.lock().map_err(|err| Error::Poisoned(err.to_string()))?.insert(base_url, url_version);
+
+        return Ok(Compatibility { url_version, endpoints });
+    }
+
+    Err(Error::NoSupportedUrlVersion(base_url))
+}
+
+pub fn backend(config: &Config) -> Result<Backend, backend::Error> {
+    Ok(Arc::new(Client::new(config)?))
 }
 
 impl IDeSscd for Client {
-    type Error = Error;
+    type Error = backend::Error;
 
     fn start_transaction(&self, request: &StartTransactionRequest) -> Result<StartTransactionResponse, Self::Error> {
-        process_response!(post!(url_version!(self.base_url, "starttransaction"), &request))
+        self.failover(|scu| process_response!(post!(self.http, url_version!(scu, "starttransaction"), &request)))
+    }
+
+    fn update_transaction(&self, request: &UpdateTransactionRequest) -> Result<UpdateTransactionResponse, Self::Error> {
+        self.failover(|scu| process_response!(post!(self.http, url_version!(scu, "updatetransaction"), &request)))
     }
 
-    fn update_transaction(&self, request: &UpdateTransactionRequest) -> Result<UpdateTransactionResponse, Error> {
-        process_response!(post!(url_version!(self.base_url, "updatetransaction"), &request))
+    fn finish_transaction(&self, request: &FinishTransactionRequest) -> Result<FinishTransactionResponse, Self::Error> {
+        self.failover(|scu| process_response!(post!(self.http, url_version!(scu, "finishtransaction"), &request)))
     }
 
-    fn finish_transaction(&self, request: &FinishTransactionRequest) -> Result<FinishTransactionResponse, Error> {
-        process_response!(post!(url_version!(self.base_url, "finishtransaction"), &request))
+    fn get_tse_info(&self) -> Result<TseInfo, Self::Error> {
+        self.failover(|scu| process_response!(get!(self.http, url_version!(scu, "tseinfo"))))
     }
 
-    fn get_tse_info(&self) -> Result<TseInfo, Error> {
-        process_response!(get!(url_version!(self.base_url, "tseinfo")))
+    fn set_tse_state(&self, state: &TseState) -> Result<TseState, Self::Error> {
+        self.failover(|scu| process_response!(post!(self.http, url_version!(scu, "tsestate"), &state)))
     }
 
-    fn set_tse_state(&self, state: &TseState) -> Result<TseState, Error> {
-        process_response!(post!(url_version!(self.base_url, "tsestate"), &state))
+    fn register_client_id(&self, request: &RegisterClientIdRequest) -> Result<RegisterClientIdResponse, Self::Error> {
+        self.failover(|scu| process_response!(post!(self.http, url_version!(scu, "registerclientid"), &request)))
     }
 
-    fn register_client_id(&self, request: &RegisterClientIdRequest) -> Result<RegisterClientIdResponse, Error> {
-        process_response!(post!(url_version!(self.base_url, "registerclientid"), &request))
+    fn unregister_client_id(&self, request: &UnregisterClientIdRequest) -> Result<UnregisterClientIdResponse, Self::Error> {
+        self.failover(|scu| process_response!(post!(self.http, url_version!(scu, "unregisterclientid"), &request)))
     }
 
-    fn unregister_client_id(&self, request: &UnregisterClientIdRequest) -> Result<UnregisterClientIdResponse, Error> {
-        process_response!(post!(url_version!(self.base_url, "unregisterclientid"), &request))
+    fn execute_set_tse_time(&self) -> Result<(), Self::Error> {
+        self.failover(|scu| process_empty_response!(post!(self.http, url_version!(scu, "executesettsetime"))))
     }
 
-    fn execute_set_tse_time(&self) -> Result<(), Error> {
-        process_empty_response!(post!(url_version!(self.base_url, "executesettsetime")))
+    fn execute_self_test(&self) -> Result<(), Self::Error> {
+        self.failover(|scu| process_empty_response!(post!(self.http, url_version!(scu, "executeselftest"))))
     }
 
-    fn execute_self_test(&self) -> Result<(), Error> {
-        process_empty_response!(post!(url_version!(self.base_url, "executeselftest")))
+    fn start_export_session(&self, request: &StartExportSessionRequest) -> Result<StartExportSessionResponse, Self::Error> {
+        self.failover(|scu| process_response!(post!(self.http, url_version!(scu, "startexportsession"), &request)))
     }
 
-    fn start_export_session(&self, request: &StartExportSessionRequest) -> Result<StartExportSessionResponse, Error> {
-        process_response!(post!(url_version!(self.base_url, "startexportsession"), &request))
+    fn start_export_session_by_time_stamp(&self, request: &StartExportSessionByTimeStampRequest) -> Result<StartExportSessionResponse, Self::Error> {
+        self.failover(|scu| process_response!(post!(self.http, url_version!(scu, "startexportsessionbytimestamp"), &request)))
     }
 
-    fn start_export_session_by_time_stamp(&self, request: &StartExportSessionByTimeStampRequest) -> Result<StartExportSessionResponse, Error> {
-        process_response!(post!(url_version!(self.base_url, "startexportsessionbytimestamp"), &request))
+    fn start_export_session_by_transaction(&self, request: &StartExportSessionByTransactionRequest) -> Result<StartExportSessionResponse, Self::Error> {
+        self.failover(|scu| process_response!(post!(self.http, url_version!(scu, "startexportsessionbytransaction"), &request)))
     }
 
-    fn start_export_session_by_transaction(&self, request: &StartExportSessionByTransactionRequest) -> Result<StartExportSessionResponse, Error> {
-        process_response!(post!(url_version!(self.base_url, "startexportsessionbytransaction"), &request))
+    fn export_data(&self, request: &ExportDataRequest) -> Result<ExportDataResponse, Self::Error> {
+        self.failover(|scu| process_response!(post!(self.http, url_version!(scu, "exportdata"), &request)))
     }
 
-    fn export_data(&self, request: &ExportDataRequest) -> Result<ExportDataResponse, Error> {
-        process_response!(post!(url_version!(self.base_url, "exportdata"), &request))
+    fn end_export_session(&self, request: &EndExportSessionRequest) -> Result<EndExportSessionResponse, Self::Error> {
+        self.failover(|scu| process_response!(post!(self.http, url_version!(scu, "endexportsession"), &request)))
     }
 
-    fn end_export_session(&self, request: &EndExportSessionRequest) -> Result<EndExportSessionResponse, Error> {
-        process_response!(post!(url_version!(self.base_url, "endexportsession"), &request))
+    fn echo(&self, request: &ScuDeEchoRequest) -> Result<ScuDeEchoResponse, Self::Error> {
+        self.failover(|scu| process_response!(post!(self.http, url_version!(scu, "echo"), &request)))
     }
 
-    fn echo(&self, request: &ScuDeEchoRequest) -> Result<ScuDeEchoResponse, Error> {
-        process_response!(post!(url_version!(self.base_url, "echo"), &request))
+    fn get_version(&self) -> Result<ScuDeVersion, Self::Error> {
+        self.failover(|scu| process_response!(get!(self.http, url_version!(scu, "version"))))
     }
 }
@@ -1,35 +1,123 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
+    fmt,
+    path::PathBuf,
     sync::{Arc, Mutex},
 };
 
 use arc_swap::{ArcSwap, ArcSwapOption};
 use log::*;
 use once_cell::sync::Lazy;
+use serde::Deserialize;
 use tini::Ini;
 
-#[derive(Debug, Clone, PartialEq)]
+mod format;
+mod validation;
+
+use format::{Document, Section, Table};
+pub use validation::Problem;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TssType {
     AsignOnline,
     CryptoVision,
 }
 
-#[derive(Debug, Clone)]
+/// A config value like a password or an api key which must never end up in a log.
+/// Its `Debug` and `Display` implementations print `***`, use [`Secret::expose`] to get the value.
+#[derive(Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Secret {
+        Secret(value)
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+/// A TSE entry, TOML and JSON sections are deserialized through [`format::Entry`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "format::Entry")]
 pub struct Config {
     pub name: String,
     pub tss_type: TssType,
     pub scu_url: String,
+    pub scu_url_fallbacks: Vec<String>,
     pub atrust_vtss_id: Option<String>,
-    pub atrust_api_key: Option<String>,
+    pub atrust_api_key: Option<Secret>,
     pub time_admin_id: Option<String>,
-    pub time_admin_pwd: Option<String>,
+    pub time_admin_pwd: Option<Secret>,
+    pub http: HttpConfig,
+    pub tls: TlsConfig,
+    pub auth: AuthConfig,
+}
+
+/// Credentials sent with every request to the SCU, e.g. when it sits behind an API gateway.
+/// The values fall back to `atrust_api_key` and `atrust_vtss_id` where it makes sense.
+#[derive(Debug, Clone, Default)]
+pub struct AuthConfig {
+    /// `none`, `api_key`, `bearer` or `basic`.
+    pub auth_type: Option<String>,
+    /// Header carrying the api key, `X-Api-Key` by default.
+    pub header: Option<String>,
+    pub api_key: Option<Secret>,
+    pub token: Option<Secret>,
+    pub username: Option<String>,
+    pub password: Option<Secret>,
+    /// Additional headers configured as `http_header_<name> = <value>`.
+    pub headers: Vec<(String, String)>,
+}
+
+/// Http settings of a TSE entry overriding those of the `config` section.
+#[derive(Debug, Clone, Default)]
+pub struct HttpConfig {
+    pub http_proxy: Option<String>,
+    pub http_proxy_username: Option<String>,
+    pub http_proxy_password: Option<Secret>,
+    pub timeout: Option<u64>,
+    pub retries: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    /// PEM file with additional CA certificates to trust.
+    pub ca_file: Option<String>,
+    /// PEM certificate (together with `client_key`) or PKCS#12 archive used for mutual TLS.
+    pub client_cert: Option<String>,
+    /// PEM PKCS#8 private key of `client_cert`.
+    pub client_key: Option<String>,
+    /// Password of a PKCS#12 `client_cert`.
+    pub client_cert_password: Option<Secret>,
+    /// SHA-256 fingerprints of which the SCU certificate has to match one.
+    pub pin_sha256: Vec<String>,
+    pub accept_invalid_certs: bool,
 }
 
-#[derive(Debug, Clone)]
+/// The `[config]` section, keys left out of a TOML or JSON section keep their default.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
 pub struct GeneralConfig {
     pub http_proxy: Option<String>,
     pub http_proxy_username: Option<String>,
-    pub http_proxy_password: Option<String>,
+    pub http_proxy_password: Option<Secret>,
     pub timeout: u64,
     pub retries: u64,
     pub logging_enabled: bool,
@@ -39,18 +127,58 @@ pub struct GeneralConfig {
     pub log_level: String,
     pub log_append: bool,
     pub log_colors: bool,
+    #[serde(default)]
     pub log_details: bool,
     pub log_stderr_colors: bool,
+    pub log_bodies: bool,
+    /// `text` or `json`, the latter writes one JSON object per line to the log file and stderr.
+    #[serde(deserialize_with = "format::non_empty")]
+    pub log_format: Option<String>,
+    /// `syslog` and/or `journald`, additionally to the log file and stderr.
+    #[serde(deserialize_with = "format::list")]
+    pub log_target: Vec<String>,
+    /// Syslog facility like `user` or `local0`.
+    pub log_facility: Option<String>,
+    /// Name the log lines are tagged with in syslog and journald.
+    pub log_identifier: Option<String>,
+    pub log_syslog_socket: Option<String>,
+    pub log_journald_socket: Option<String>,
+    /// Size in bytes after which the log file is rotated.
+    #[serde(deserialize_with = "format::non_zero")]
+    pub log_rotate_size: Option<u64>,
+    /// `day`, `hour`, `minute` or `second`, rotates the log file when a new one begins.
+    #[serde(deserialize_with = "format::non_empty")]
+    pub log_rotate_age: Option<String>,
+    /// Number of rotated log files kept, all are kept if not set.
+    #[serde(deserialize_with = "format::non_zero")]
+    pub log_keep_files: Option<usize>,
+    pub log_compress: bool,
+    /// Seconds between two heartbeats checking all TSEs, `0` disables the heartbeat.
     pub msg_upload_interval: u64,
+    /// Endpoint the health of all TSEs is posted to on every heartbeat.
+    #[serde(deserialize_with = "format::non_empty")]
+    pub heartbeat_url: Option<String>,
     pub max_audit_log_size: u32,
+    pub tse_info_cache_ttl: u64,
+    pub scu_failover_cooldown: u64,
+    pub circuit_breaker_threshold: u32,
+    pub circuit_breaker_reset: u64,
+    /// Rejects a config file with problems instead of only logging them.
+    pub strict_config: bool,
+    /// Seconds between two checks whether the config file changed, `0` disables the hot reload.
+    pub config_reload_interval: u64,
 }
 
 pub const DEFAULT_TIMEOUT_VALUE: u64 = 1_500;
 pub const DEFAULT_NUMBER_OF_RETRIES: u64 = 1;
 pub const DEFAULT_MSG_UPLOAD_INTERVAL: u64 = 60 * 60 * 24;
 pub const DEFAULT_MAX_AUDIT_LOG_SIZE: u32 = 128;
+pub const DEFAULT_TSE_INFO_CACHE_TTL: u64 = 30;
+pub const DEFAULT_SCU_FAILOVER_COOLDOWN: u64 = 60;
+pub const DEFAULT_CIRCUIT_BREAKER_THRESHOLD: u32 = 3;
+pub const DEFAULT_CIRCUIT_BREAKER_RESET: u64 = 30;
 
-impl GeneralConfig {
+impl Default for GeneralConfig {
     fn default() -> GeneralConfig {
         GeneralConfig {
             logging_enabled: false,
@@ -62,13 +190,31 @@ impl GeneralConfig {
             log_colors: false,
             log_details: true,
             log_stderr_colors: false,
+            log_bodies: false,
+            log_format: None,
+            log_target: vec![],
+            log_facility: None,
+            log_identifier: None,
+            log_syslog_socket: None,
+            log_journald_socket: None,
+            log_rotate_size: None,
+            log_rotate_age: None,
+            log_keep_files: None,
+            log_compress: false,
             http_proxy: None,
             http_proxy_username: None,
             http_proxy_password: None,
             timeout: DEFAULT_TIMEOUT_VALUE,
             retries: DEFAULT_NUMBER_OF_RETRIES,
             msg_upload_interval: DEFAULT_MSG_UPLOAD_INTERVAL,
+            heartbeat_url: None,
             max_audit_log_size: DEFAULT_MAX_AUDIT_LOG_SIZE,
+            tse_info_cache_ttl: DEFAULT_TSE_INFO_CACHE_TTL,
+            scu_failover_cooldown: DEFAULT_SCU_FAILOVER_COOLDOWN,
+            circuit_breaker_threshold: DEFAULT_CIRCUIT_BREAKER_THRESHOLD,
+            circuit_breaker_reset: DEFAULT_CIRCUIT_BREAKER_RESET,
+            strict_config: false,
+            config_reload_interval: 0,
         }
     }
 }
@@ -79,28 +225,199 @@ impl Default for Config {
             name: String::from(""),
             tss_type: TssType::AsignOnline,
             scu_url: String::from(""),
+            scu_url_fallbacks: vec![],
             atrust_vtss_id: None,
             atrust_api_key: None,
             time_admin_id: None,
             time_admin_pwd: None,
+            http: HttpConfig::default(),
+            tls: TlsConfig::default(),
+            auth: AuthConfig::default(),
         }
     }
 }
 
-pub static CONFIG_FILE: Lazy<ArcSwap<String>> = Lazy::new(|| ArcSwap::from_pointee(String::from("asigntseonline.conf")));
+impl Config {
+    /// All SCU urls of this entry in the order they are tried.
+    pub fn scu_urls(&self) -> Vec<String> {
+        std::iter::once(self.scu_url.clone()).chain(self.scu_url_fallbacks.iter().cloned()).collect()
+    }
+}
+
+/// Splits the comma separated `scu_url` and `scu_url_fallback` values into the primary url and its fallbacks.
+fn parse_scu_urls(scu_url: &str, scu_url_fallback: Option<&str>) -> (String, Vec<String>) {
+    let mut scu_urls = scu_url.split(',').chain(scu_url_fallback.unwrap_or("").split(',')).map(str::trim).filter(|url| !url.is_empty()).map(String::from);
+
+    (scu_urls.next().unwrap_or_default(), scu_urls.collect())
+}
+
+fn parse_http_config(get: impl Fn(&str) -> Option<String>) -> HttpConfig {
+    HttpConfig {
+        http_proxy: get("http_proxy"),
+        http_proxy_username: get("http_proxy_username"),
+        http_proxy_password: get("http_proxy_password").map(Secret::from),
+        timeout: get("timeout").and_then(|s| s.parse().ok()),
+        retries: get("retries").and_then(|s| s.parse().ok()),
+    }
+}
+
+fn parse_auth_config<'a>(get: impl Fn(&str) -> Option<String>, section: impl Iterator<Item = (&'a String, &'a String)>) -> AuthConfig {
+    AuthConfig {
+        auth_type: get("auth_type"),
+        header: get("auth_header"),
+        api_key: get("auth_api_key").map(Secret::from),
+        token: get("auth_token").map(Secret::from),
+        username: get("auth_username"),
+        password: get("auth_password").map(Secret::from),
+        headers: section.filter_map(|(key, value)| key.strip_prefix("http_header_").map(|name| (name.to_string(), value.to_string()))).collect(),
+    }
+}
+
+fn parse_tls_config(get: impl Fn(&str) -> Option<String>) -> TlsConfig {
+    TlsConfig {
+        ca_file: get("tls_ca_file"),
+        client_cert: get("tls_client_cert"),
+        client_key: get("tls_client_key"),
+        client_cert_password: get("tls_client_cert_password").map(Secret::from),
+        pin_sha256: get("tls_pin_sha256").map(|pins| pins.split(',').map(str::trim).filter(|pin| !pin.is_empty()).map(String::from).collect()).unwrap_or_default(),
+        accept_invalid_certs: get("tls_accept_invalid_certs").map(|s| s.parse().unwrap_or(false)).unwrap_or(false),
+    }
+}
+
+const CONFIG_FILE_NAME: &str = "asigntseonline.conf";
+
+/// Points to the config file, it takes precedence over all other locations.
+const CONFIG_FILE_ENV: &str = "ASIGNTSE_CONFIG";
+
+/// `ASIGNTSE_<KEY>` overrides a key of the `[config]` section or, if it is not a general setting, of the default TSE entry. `ASIGNTSE_<SECTION>__<KEY>` overrides a key of any section.
+const ENV_PREFIX: &str = "ASIGNTSE_";
+
+/// The directory of the loaded shared library.
+#[cfg(unix)]
+fn library_dir() -> Option<PathBuf> {
+    let mut info = std::mem::MaybeUninit::<libc::Dl_info>::uninit();
+
+    if unsafe { libc::dladdr(library_dir as *const libc::c_void, info.as_mut_ptr()) } == 0 {
+        return None;
+    }
+
+    let file_name = unsafe { info.assume_init() }.dli_fname;
+    if file_name.is_null() {
+        return None;
+    }
+
+    let path = unsafe { std::ffi::CStr::from_ptr(file_name) }.to_string_lossy().to_string();
+
+    PathBuf::from(path).canonicalize().ok()?.parent().map(PathBuf::from)
+}
+
+/// The directory of the loaded shared library.
+#[cfg(windows)]
+fn library_dir() -> Option<PathBuf> {
+    use std::os::windows::ffi::OsStringExt;
+
+    const GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS: u32 = 0x4;
+    const GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT: u32 = 0x2;
+
+    extern "system" {
+        fn GetModuleHandleExW(flags: u32, module_name: *const u16, module: *mut *mut libc::c_void) -> i32;
+        fn GetModuleFileNameW(module: *mut libc::c_void, file_name: *mut u16, size: u32) -> u32;
+    }
+
+    let mut module = std::ptr::null_mut();
+    if unsafe { GetModuleHandleExW(GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT, library_dir as *const u16, &mut module) } == 0 {
+        return None;
+    }
+
+    let mut file_name = vec![0u16; 32 * 1024];
+    let length = unsafe { GetModuleFileNameW(module, file_name.as_mut_ptr(), file_name.len() as u32) } as usize;
+    if length == 0 {
+        return None;
+    }
+
+    PathBuf::from(std::ffi::OsString::from_wide(&file_name[..length])).parent().map(PathBuf::from)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn library_dir() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(unix)]
+fn system_dir() -> Option<PathBuf> {
+    Some(PathBuf::from("/etc/asigntse"))
+}
+
+#[cfg(not(unix))]
+fn system_dir() -> Option<PathBuf> {
+    std::env::var_os("ProgramData").map(|program_data| PathBuf::from(program_data).join("asigntse"))
+}
+
+/// Looks for the config file in `ASIGNTSE_CONFIG`, next to the loaded library, in the working directory and in the system config directory, in this order.
+/// Falls back to `asigntseonline.conf` in the working directory if it is found nowhere.
+pub fn discover_config_file() -> String {
+    if let Some(path) = std::env::var(CONFIG_FILE_ENV).ok().filter(|path| !path.is_empty()) {
+        return path;
+    }
+
+    library_dir()
+        .into_iter()
+        .chain(Some(PathBuf::new()))
+        .chain(system_dir())
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|| String::from(CONFIG_FILE_NAME))
+}
+
+pub static CONFIG_FILE: Lazy<ArcSwap<String>> = Lazy::new(|| ArcSwap::from_pointee(discover_config_file()));
 
 pub static GENERAL_CONFIG: Lazy<Mutex<GeneralConfig>> = Lazy::new(|| Mutex::new(GeneralConfig::default()));
 
-fn set_ini() -> Option<Ini> {
-    Ini::from_file(&**CONFIG_FILE.load()).ok()
+fn set_document() -> Option<Document> {
+    apply_env_overrides(format::read(&CONFIG_FILE.load()).ok(), std::env::vars())
+}
+
+/// The section used as `default` TSE entry: the only section of the file or the one named `default`.
+fn default_section(document: &Document) -> String {
+    match document.section_names().as_slice() {
+        [name] => name.clone(),
+        _ => String::from("default"),
+    }
+}
+
+/// Overrides keys of the config file with the `ASIGNTSE_*` environment variables, see [`ENV_PREFIX`].
+/// Without a config file the environment alone is used.
+fn apply_env_overrides(document: Option<Document>, vars: impl Iterator<Item = (String, String)>) -> Option<Document> {
+    let overrides: Vec<(String, String)> = vars.filter(|(name, _)| name != CONFIG_FILE_ENV).filter_map(|(name, value)| name.strip_prefix(ENV_PREFIX).map(|key| (key.to_lowercase(), value))).collect();
+
+    if overrides.is_empty() {
+        return document;
+    }
+
+    let mut document = document.unwrap_or_else(|| Document::Ini(Ini::new()));
+    let default = default_section(&document);
+
+    for (key, value) in overrides {
+        let (section, key) = match key.split_once("__") {
+            Some((section, key)) => (section.to_string(), key.to_string()),
+            None if validation::is_general_key(&key) => (String::from("config"), key),
+            None => (default.clone(), key),
+        };
+
+        debug!("{} of [{}] is overridden by the environment", key, section);
+        document = document.set(section, key, value);
+    }
+
+    Some(document)
 }
 
-static INI: Lazy<ArcSwapOption<Ini>> = Lazy::new(|| ArcSwapOption::from(set_ini().map(Arc::new)));
+static DOCUMENT: Lazy<ArcSwapOption<Document>> = Lazy::new(|| ArcSwapOption::from(set_document().map(Arc::new)));
 
 fn set_configs() -> HashMap<String, Config> {
     let mut gconf = GENERAL_CONFIG.lock().unwrap();
 
-    parse_config(&mut gconf)
+    parse_config(DOCUMENT.load_full().as_deref(), &mut gconf)
 }
 
 pub static CONFIGS: Lazy<Mutex<HashMap<String, Config>>> = Lazy::new(|| Mutex::new(set_configs()));
@@ -115,50 +432,187 @@ pub fn get_tss(name: &str) -> Option<Config> {
         name: c.name.to_string(),
         tss_type: c.tss_type,
         scu_url: c.scu_url.to_string(),
+        scu_url_fallbacks: c.scu_url_fallbacks,
         atrust_vtss_id: c.atrust_vtss_id,
         atrust_api_key: c.atrust_api_key,
         time_admin_id: c.time_admin_id,
         time_admin_pwd: c.time_admin_pwd,
+        http: c.http,
+        tls: c.tls,
+        auth: c.auth,
     })
 }
 
+pub fn get_all_tss() -> Vec<Config> {
+    let mut configs: Vec<Config> = ok_or_return!(CONFIGS.lock(), |_| vec![]).values().cloned().collect();
+    configs.sort_by(|a, b| a.name.cmp(&b.name));
+
+    configs
+}
+
 pub fn has_read_config() -> bool {
     !CONFIGS.lock().unwrap().is_empty()
 }
 
-pub fn read_config() -> bool {
-    INI.store(set_ini().map(Arc::new));
-    *try_or_return!(|| CONFIGS.lock(), |_| false) = set_configs();
+/// Re-reads the config file and replaces only the entry of the given TSE, leaving the general config and all other entries untouched.
+/// The file is checked like on [`reload`], with `strict_config` set a file with problems is rejected and the problems returned.
+/// Other changes of the file are left to the hot reload, which is why the file is not remembered as the current one.
+pub fn reload_tss(name: &str) -> Result<Option<Config>, Vec<Problem>> {
+    let path = CONFIG_FILE.load_full();
+    let document = set_document();
 
-    true
-}
+    let mut gconf = ok_or_return!(GENERAL_CONFIG.lock(), |_| Ok(None)).clone();
+    let config = parse_config(document.as_ref(), &mut gconf).remove(name);
 
-pub fn set_config_file(path: &str) -> bool {
-    CONFIG_FILE.store(Arc::new(path.to_string()));
+    let problems = validation::validate(&path, document.as_ref());
+    if !accepts(&problems, gconf.strict_config) {
+        return Err(problems);
+    }
+
+    let mut configs = ok_or_return!(CONFIGS.lock(), |_| Ok(None));
+    match &config {
+        Some(config) => configs.insert(name.to_string(), config.clone()),
+        None => configs.remove(name),
+    };
 
-    read_config()
+    Ok(config)
 }
 
-fn get_default_entry() -> Option<Config> {
-    let ini = INI.load_full()?;
+/// Re-reads the config file and replaces the general config and all TSE entries at once.
+/// Problems are logged, with `strict_config` set a config with problems is not used and the previous one is kept, see [`check`].
+/// Returns the sections and keys which changed or `None` if the config was rejected.
+pub fn reload() -> Option<Vec<String>> {
+    let path = CONFIG_FILE.load_full();
+    let document = set_document();
 
-    if ini.iter().count() == 0 {
+    let mut configs = ok_or_return!(CONFIGS.lock(), |_| None);
+    let mut general_config = ok_or_return!(GENERAL_CONFIG.lock(), |_| None);
+
+    // `cfgSetConfigFile` switched to another file meanwhile
+    if !Arc::ptr_eq(&path, &CONFIG_FILE.load_full()) {
+        return Some(vec![]);
+    }
+
+    let changes = diff(DOCUMENT.load_full().as_deref(), document.as_ref());
+    if changes.is_empty() {
+        return Some(changes);
+    }
+
+    let mut reloaded = general_config.clone();
+    let reloaded_configs = parse_config(document.as_ref(), &mut reloaded);
+
+    if !accepts(&validation::validate(&path, document.as_ref()), reloaded.strict_config) {
         return None;
     }
 
-    let mut default = String::from("default");
-    if ini.iter().count() == 1 {
-        let (n, _) = some_or_return!(ini.iter().next(), None);
-        default = n.to_string();
-    } else {
-        for (section, _) in ini.iter() {
-            if section == "default" {
-                default = section.to_string();
+    *configs = reloaded_configs;
+    *general_config = reloaded;
+    DOCUMENT.store(document.map(Arc::new));
+
+    Some(changes)
+}
+
+/// The sections and keys which differ between two configs, values are left out as they might be secrets.
+fn diff(old: Option<&Document>, new: Option<&Document>) -> Vec<String> {
+    let sections = |document: Option<&Document>| -> BTreeMap<String, BTreeMap<String, serde_json::Value>> { document.map(|document| document.sections().into_iter().map(|(name, section)| (name, section.0.into_iter().collect())).collect()).unwrap_or_default() };
+    let (old, new) = (sections(old), sections(new));
+
+    let mut changes: Vec<String> = old.keys().filter(|name| !new.contains_key(*name)).map(|name| format!("[{}] removed", name)).collect();
+
+    for (name, section) in new.iter() {
+        let old_section = match old.get(name) {
+            Some(old_section) => old_section,
+            None => {
+                changes.push(format!("[{}] added", name));
+                continue;
             }
+        };
+
+        changes.extend(old_section.keys().filter(|key| !section.contains_key(*key)).map(|key| format!("[{}] {} removed", name, key)));
+
+        for (key, value) in section.iter() {
+            match old_section.get(key) {
+                None => changes.push(format!("[{}] {} added", name, key)),
+                Some(old_value) if old_value != value => changes.push(format!("[{}] {} changed", name, key)),
+                Some(_) => {}
+            }
+        }
+    }
+
+    changes
+}
+
+/// Checks the current config file and the config read from it and the environment, see [`validation::validate`].
+pub fn validate() -> Vec<Problem> {
+    validation::validate(&CONFIG_FILE.load(), DOCUMENT.load_full().as_deref())
+}
+
+/// Logs every problem of the current config, returns `false` if there are any and `strict_config` is set.
+pub fn check() -> bool {
+    let strict = GENERAL_CONFIG.lock().map(|general_config| general_config.strict_config).unwrap_or(false);
+
+    accepts(&validate(), strict)
+}
+
+/// Logs every problem, returns `false` if there are any and `strict` is set.
+fn accepts(problems: &[Problem], strict: bool) -> bool {
+    for problem in problems.iter() {
+        match strict {
+            true => error!("invalid config: {}", problem),
+            false => warn!("invalid config: {}", problem),
         }
     }
 
+    !strict || problems.is_empty()
+}
+
+/// Logs the general config and all TSE entries in effect at `debug`, secrets are printed as `***`.
+pub fn log_effective() {
+    let path = CONFIG_FILE.load_full();
+    let general_config = ok_or_return!(GENERAL_CONFIG.lock(), |_| ()).clone();
+
+    debug!("config in effect from {}: {:?}", path, general_config);
+    for config in get_all_tss() {
+        debug!("config in effect from {}: {:?}", path, config);
+    }
+}
+
+/// Uses the config file at the given path, an empty path looks the config file up again like on startup.
+/// The file is checked before it is used, see [`check`], if it is rejected the previous config is kept and `false` returned.
+pub fn set_config_file(path: &str) -> bool {
+    let path = match path {
+        "" => discover_config_file(),
+        path => path.to_string(),
+    };
+    let document = apply_env_overrides(format::read(&path).ok(), std::env::vars());
+
+    let mut configs = ok_or_return!(CONFIGS.lock(), |_| false);
+    let mut general_config = ok_or_return!(GENERAL_CONFIG.lock(), |_| false);
+
+    let mut parsed = general_config.clone();
+    let parsed_configs = parse_config(document.as_ref(), &mut parsed);
+
+    if !accepts(&validation::validate(&path, document.as_ref()), parsed.strict_config) {
+        error!("{} is invalid, keeping the previous config", path);
+        return false;
+    }
+
+    CONFIG_FILE.store(Arc::new(path));
+    DOCUMENT.store(document.map(Arc::new));
+    *configs = parsed_configs;
+    *general_config = parsed;
+
+    true
+}
+
+fn get_default_entry(ini: &Ini, default: String) -> Option<Config> {
+    if ini.iter().count() == 0 {
+        return None;
+    }
+
     let scu_url: String = some_or_return!(ini.get(&default, "scu_url"), None);
+    let scu_url_fallback: Option<String> = ini.get(&default, "scu_url_fallback");
+    let (scu_url, scu_url_fallbacks) = parse_scu_urls(&scu_url, scu_url_fallback.as_deref());
 
     let t: Option<String> = ini.get(&default, &String::from("tss_type"));
     t.as_ref()?;
@@ -175,120 +629,221 @@ fn get_default_entry() -> Option<Config> {
     );
 
     let atrust_vtss_id: Option<String> = ini.get(&default, "atrust_vtss_id");
-    let atrust_api_key: Option<String> = ini.get(&default, "atrust_api_key");
+    let atrust_api_key: Option<Secret> = ini.get::<String>(&default, "atrust_api_key").map(Secret::from);
 
     let time_admin_id: Option<String> = ini.get(&default, "time_admin_id");
-    let time_admin_pwd: Option<String> = ini.get(&default, "time_admin_pwd");
+    let time_admin_pwd: Option<Secret> = ini.get::<String>(&default, "time_admin_pwd").map(Secret::from);
+
+    let http = parse_http_config(|key| ini.get(&default, key));
+    let tls = parse_tls_config(|key| ini.get(&default, key));
+    let auth = parse_auth_config(|key| ini.get(&default, key), ini.section_iter(&default));
 
     Some(Config {
         tss_type,
         name: default,
         scu_url,
+        scu_url_fallbacks,
         atrust_api_key,
         atrust_vtss_id,
         time_admin_id,
         time_admin_pwd,
+        http,
+        tls,
+        auth,
     })
 }
 
-fn parse_config(gconf: &mut GeneralConfig) -> HashMap<String, Config> {
-    let mut hm: HashMap<String, Config> = HashMap::new();
+fn parse_config(document: Option<&Document>, gconf: &mut GeneralConfig) -> HashMap<String, Config> {
+    let default = document.map(default_section).unwrap_or_default();
 
-    if let Some(default_cfg) = get_default_entry() {
-        hm.insert("default".to_string(), default_cfg);
+    match document {
+        Some(Document::Ini(ini)) => parse_ini(ini, default, gconf),
+        Some(Document::Typed(sections)) => parse_typed(sections, default, gconf),
+        None => HashMap::new(),
     }
+}
 
-    if let Some(ini) = INI.load_full() {
-        for (s_name, section) in ini.iter() {
-            let sec: HashMap<&String, &String> = section.iter().collect();
-
-            if s_name == "default" {
-                continue;
-            }
+/// Deserializes the `[config]` section into the general config and every other section into a TSE entry, sections which can not be deserialized are left out like in [`parse_ini`].
+fn parse_typed(sections: &Table<Section>, default: String, gconf: &mut GeneralConfig) -> HashMap<String, Config> {
+    let mut configs = HashMap::new();
 
-            if s_name == "config" {
-                let http_proxy = sec.get(&String::from("http_proxy")).map(|s| s.to_string());
-                let http_proxy_username = sec.get(&String::from("http_proxy_username")).map(|s| s.to_string());
-                let http_proxy_password = sec.get(&String::from("http_proxy_password")).map(|s| s.to_string());
-                let timeout = sec.get(&String::from("timeout")).map(|s| s.parse().unwrap_or(DEFAULT_TIMEOUT_VALUE)).unwrap_or(DEFAULT_TIMEOUT_VALUE);
-                let retries = sec.get(&String::from("retries")).map(|s| s.parse().unwrap_or(DEFAULT_NUMBER_OF_RETRIES)).unwrap_or(DEFAULT_NUMBER_OF_RETRIES);
-                let logging_enabled = sec.get(&String::from("logging_enabled")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
-                let logging_stderr = sec.get(&String::from("logging_stderr")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
-                let logging_file = sec.get(&String::from("logging_file")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
-                let log_dir = sec.get(&String::from("log_dir")).map(|s| s.to_string()).unwrap_or_else(|| String::from("."));
-                let log_level = sec.get(&String::from("log_level")).map(|s| s.to_string()).unwrap_or_else(|| String::from("trace"));
-                let log_append = sec.get(&String::from("log_append")).map(|s| s.to_string().parse().unwrap_or(true)).unwrap_or(true);
-                let log_colors = sec.get(&String::from("log_colors")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
-                let log_details = sec.get(&String::from("log_details")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
-                let log_stderr_colors = sec.get(&String::from("log_stderr_colors")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
-                let msg_upload_interval = sec.get(&String::from("msg_upload_interval")).map(|s| s.to_string().parse().unwrap_or(DEFAULT_MSG_UPLOAD_INTERVAL)).unwrap_or(DEFAULT_MSG_UPLOAD_INTERVAL);
-                let max_audit_log_size = sec.get(&String::from("max_audit_log_size")).map(|s| s.to_string().parse().unwrap_or(DEFAULT_MAX_AUDIT_LOG_SIZE)).unwrap_or(DEFAULT_MAX_AUDIT_LOG_SIZE);
-
-                gconf.http_proxy = http_proxy;
-                gconf.http_proxy_username = http_proxy_username;
-                gconf.http_proxy_password = http_proxy_password;
-                gconf.timeout = timeout;
-                gconf.retries = retries;
-                gconf.logging_enabled = logging_enabled;
-                gconf.logging_stderr = logging_stderr;
-                gconf.logging_file = logging_file;
-                gconf.log_dir = log_dir;
-                gconf.log_level = log_level;
-                gconf.log_append = log_append;
-                gconf.log_colors = log_colors;
-                gconf.log_details = log_details;
-                gconf.log_stderr_colors = log_stderr_colors;
-                gconf.msg_upload_interval = msg_upload_interval;
-                gconf.max_audit_log_size = max_audit_log_size;
+    for (section_name, section) in sections.0.iter() {
+        if section_name == "config" {
+            match section.parse() {
+                Ok(general_config) => *gconf = general_config,
+                Err(err) => warn!("[config] is ignored: {}", err),
             }
+            continue;
+        }
 
-            if !sec.contains_key(&"tss_type".to_string()) {
+        let mut config: Config = match section.parse() {
+            Ok(config) => config,
+            Err(err) => {
+                warn!("[{}] is ignored: {}", section_name, err);
                 continue;
             }
+        };
 
-            if !sec.contains_key(&"scu_url".to_string()) {
-                continue;
-            }
+        if *section_name == default {
+            configs.insert(String::from("default"), Config { name: default.clone(), ..config.clone() });
+        }
 
-            let mut tss_type: Option<TssType> = None;
-            if let Some(t) = sec.get(&String::from("tss_type")) {
-                tss_type = match t.as_str() {
-                    "1" => Some(TssType::AsignOnline),
-                    "2" => Some(TssType::CryptoVision),
-                    _ => None,
-                };
-
-                if tss_type.is_none() {
-                    continue;
-                }
-            }
+        if section_name == "default" {
+            continue;
+        }
+
+        if config.name.is_empty() {
+            config.name = section_name.clone();
+        }
 
-            let name = match sec.get(&"name".to_string()) {
-                Some(n) => n,
-                None => s_name,
+        configs.insert(config.name.clone(), config);
+    }
+
+    configs
+}
+
+fn parse_ini(ini: &Ini, default: String, gconf: &mut GeneralConfig) -> HashMap<String, Config> {
+    let mut hm: HashMap<String, Config> = HashMap::new();
+
+    if let Some(default_cfg) = get_default_entry(ini, default) {
+        hm.insert("default".to_string(), default_cfg);
+    }
+
+    for (s_name, section) in ini.iter() {
+        let sec: HashMap<&String, &String> = section.iter().collect();
+
+        if s_name == "default" {
+            continue;
+        }
+
+        if s_name == "config" {
+            let http_proxy = sec.get(&String::from("http_proxy")).map(|s| s.to_string());
+            let http_proxy_username = sec.get(&String::from("http_proxy_username")).map(|s| s.to_string());
+            let http_proxy_password = sec.get(&String::from("http_proxy_password")).map(|s| Secret::from(s.to_string()));
+            let timeout = sec.get(&String::from("timeout")).map(|s| s.parse().unwrap_or(DEFAULT_TIMEOUT_VALUE)).unwrap_or(DEFAULT_TIMEOUT_VALUE);
+            let retries = sec.get(&String::from("retries")).map(|s| s.parse().unwrap_or(DEFAULT_NUMBER_OF_RETRIES)).unwrap_or(DEFAULT_NUMBER_OF_RETRIES);
+            let logging_enabled = sec.get(&String::from("logging_enabled")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
+            let logging_stderr = sec.get(&String::from("logging_stderr")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
+            let logging_file = sec.get(&String::from("logging_file")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
+            let log_dir = sec.get(&String::from("log_dir")).map(|s| s.to_string()).unwrap_or_else(|| String::from("."));
+            let log_level = sec.get(&String::from("log_level")).map(|s| s.to_string()).unwrap_or_else(|| String::from("trace"));
+            let log_append = sec.get(&String::from("log_append")).map(|s| s.to_string().parse().unwrap_or(true)).unwrap_or(true);
+            let log_colors = sec.get(&String::from("log_colors")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
+            let log_details = sec.get(&String::from("log_details")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
+            let log_stderr_colors = sec.get(&String::from("log_stderr_colors")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
+            let log_bodies = sec.get(&String::from("log_bodies")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
+            let log_format = sec.get(&String::from("log_format")).map(|s| s.to_string()).filter(|format| !format.is_empty());
+            let log_target = sec.get(&String::from("log_target")).map(|s| s.split(',').map(str::trim).filter(|target| !target.is_empty()).map(String::from).collect()).unwrap_or_default();
+            let log_facility = sec.get(&String::from("log_facility")).map(|s| s.to_string());
+            let log_identifier = sec.get(&String::from("log_identifier")).map(|s| s.to_string());
+            let log_syslog_socket = sec.get(&String::from("log_syslog_socket")).map(|s| s.to_string());
+            let log_journald_socket = sec.get(&String::from("log_journald_socket")).map(|s| s.to_string());
+            let log_rotate_size = sec.get(&String::from("log_rotate_size")).and_then(|s| s.to_string().parse().ok()).filter(|size| *size > 0);
+            let log_rotate_age = sec.get(&String::from("log_rotate_age")).map(|s| s.to_string()).filter(|age| !age.is_empty());
+            let log_keep_files = sec.get(&String::from("log_keep_files")).and_then(|s| s.to_string().parse().ok()).filter(|keep| *keep > 0);
+            let log_compress = sec.get(&String::from("log_compress")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
+            let msg_upload_interval = sec.get(&String::from("msg_upload_interval")).map(|s| s.to_string().parse().unwrap_or(DEFAULT_MSG_UPLOAD_INTERVAL)).unwrap_or(DEFAULT_MSG_UPLOAD_INTERVAL);
+            let heartbeat_url = sec.get(&String::from("heartbeat_url")).map(|s| s.to_string()).filter(|url| !url.is_empty());
+            let max_audit_log_size = sec.get(&String::from("max_audit_log_size")).map(|s| s.to_string().parse().unwrap_or(DEFAULT_MAX_AUDIT_LOG_SIZE)).unwrap_or(DEFAULT_MAX_AUDIT_LOG_SIZE);
+            let tse_info_cache_ttl = sec.get(&String::from("tse_info_cache_ttl")).map(|s| s.to_string().parse().unwrap_or(DEFAULT_TSE_INFO_CACHE_TTL)).unwrap_or(DEFAULT_TSE_INFO_CACHE_TTL);
+            let scu_failover_cooldown = sec.get(&String::from("scu_failover_cooldown")).map(|s| s.to_string().parse().unwrap_or(DEFAULT_SCU_FAILOVER_COOLDOWN)).unwrap_or(DEFAULT_SCU_FAILOVER_COOLDOWN);
+            let circuit_breaker_threshold = sec.get(&String::from("circuit_breaker_threshold")).map(|s| s.to_string().parse().unwrap_or(DEFAULT_CIRCUIT_BREAKER_THRESHOLD)).unwrap_or(DEFAULT_CIRCUIT_BREAKER_THRESHOLD);
+            let circuit_breaker_reset = sec.get(&String::from("circuit_breaker_reset")).map(|s| s.to_string().parse().unwrap_or(DEFAULT_CIRCUIT_BREAKER_RESET)).unwrap_or(DEFAULT_CIRCUIT_BREAKER_RESET);
+            let strict_config = sec.get(&String::from("strict_config")).map(|s| s.to_string().parse().unwrap_or(false)).unwrap_or(false);
+            let config_reload_interval = sec.get(&String::from("config_reload_interval")).map(|s| s.to_string().parse().unwrap_or(0)).unwrap_or(0);
+
+            gconf.http_proxy = http_proxy;
+            gconf.http_proxy_username = http_proxy_username;
+            gconf.http_proxy_password = http_proxy_password;
+            gconf.timeout = timeout;
+            gconf.retries = retries;
+            gconf.logging_enabled = logging_enabled;
+            gconf.logging_stderr = logging_stderr;
+            gconf.logging_file = logging_file;
+            gconf.log_dir = log_dir;
+            gconf.log_level = log_level;
+            gconf.log_append = log_append;
+            gconf.log_colors = log_colors;
+            gconf.log_details = log_details;
+            gconf.log_stderr_colors = log_stderr_colors;
+            gconf.log_bodies = log_bodies;
+            gconf.log_format = log_format;
+            gconf.log_target = log_target;
+            gconf.log_facility = log_facility;
+            gconf.log_identifier = log_identifier;
+            gconf.log_syslog_socket = log_syslog_socket;
+            gconf.log_journald_socket = log_journald_socket;
+            gconf.log_rotate_size = log_rotate_size;
+            gconf.log_rotate_age = log_rotate_age;
+            gconf.log_keep_files = log_keep_files;
+            gconf.log_compress = log_compress;
+            gconf.msg_upload_interval = msg_upload_interval;
+            gconf.heartbeat_url = heartbeat_url;
+            gconf.max_audit_log_size = max_audit_log_size;
+            gconf.tse_info_cache_ttl = tse_info_cache_ttl;
+            gconf.scu_failover_cooldown = scu_failover_cooldown;
+            gconf.circuit_breaker_threshold = circuit_breaker_threshold;
+            gconf.circuit_breaker_reset = circuit_breaker_reset;
+            gconf.strict_config = strict_config;
+            gconf.config_reload_interval = config_reload_interval;
+        }
+
+        if !sec.contains_key(&"tss_type".to_string()) {
+            continue;
+        }
+
+        if !sec.contains_key(&"scu_url".to_string()) {
+            continue;
+        }
+
+        let mut tss_type: Option<TssType> = None;
+        if let Some(t) = sec.get(&String::from("tss_type")) {
+            tss_type = match t.as_str() {
+                "1" => Some(TssType::AsignOnline),
+                "2" => Some(TssType::CryptoVision),
+                _ => None,
             };
 
-            let vtss_id = sec.get(&String::from("atrust_vtss_id")).map(|s| s.to_string());
-            let atrust_api_key = sec.get(&String::from("atrust_api_key")).map(|s| s.to_string());
-
-            let time_admin_id = sec.get(&String::from("time_admin_id")).map(|s| s.to_string());
-            let time_admin_pwd = sec.get(&String::from("time_admin_pwd")).map(|s| s.to_string());
-
-            let scu_url = sec.get(&String::from("scu_url")).map(|s| s.to_string());
-
-            hm.insert(
-                name.to_string(),
-                Config {
-                    name: name.to_string(),
-                    tss_type: tss_type.unwrap_or(TssType::AsignOnline),
-                    scu_url: scu_url.unwrap_or_else(|| String::from("")),
-                    atrust_vtss_id: vtss_id,
-                    atrust_api_key,
-                    time_admin_id,
-                    time_admin_pwd,
-                },
-            );
+            if tss_type.is_none() {
+                continue;
+            }
         }
+
+        let name = match sec.get(&"name".to_string()) {
+            Some(n) => n,
+            None => s_name,
+        };
+
+        let vtss_id = sec.get(&String::from("atrust_vtss_id")).map(|s| s.to_string());
+        let atrust_api_key = sec.get(&String::from("atrust_api_key")).map(|s| Secret::from(s.to_string()));
+
+        let time_admin_id = sec.get(&String::from("time_admin_id")).map(|s| s.to_string());
+        let time_admin_pwd = sec.get(&String::from("time_admin_pwd")).map(|s| Secret::from(s.to_string()));
+
+        let http = parse_http_config(|key| sec.get(&key.to_string()).map(|s| s.to_string()));
+        let tls = parse_tls_config(|key| sec.get(&key.to_string()).map(|s| s.to_string()));
+        let auth = parse_auth_config(|key| sec.get(&key.to_string()).map(|s| s.to_string()), section.iter());
+
+        let scu_url = sec.get(&String::from("scu_url")).map(|s| s.to_string()).unwrap_or_else(|| String::from(""));
+        let scu_url_fallback = sec.get(&String::from("scu_url_fallback")).map(|s| s.to_string());
+        let (scu_url, scu_url_fallbacks) = parse_scu_urls(&scu_url, scu_url_fallback.as_deref());
+
+        hm.insert(
+            name.to_string(),
+            Config {
+                name: name.to_string(),
+                tss_type: tss_type.unwrap_or(TssType::AsignOnline),
+                scu_url,
+                scu_url_fallbacks,
+                atrust_vtss_id: vtss_id,
+                atrust_api_key,
+                time_admin_id,
+                time_admin_pwd,
+                http,
+                tls,
+                auth,
+            },
+        );
     }
 
     hm
@@ -0,0 +1,275 @@
+use std::{collections::BTreeMap, fmt, fs, marker::PhantomData, path::Path};
+
+use serde::{
+    de::{self, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use tini::Ini;
+
+use super::{AuthConfig, Config, HttpConfig, Secret, TlsConfig, TssType};
+
+/// The format of a config file, detected by its extension. Files without a `.toml` or `.json` extension are read as INI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Ini,
+    Toml,
+    Json,
+}
+
+impl Format {
+    pub fn of(path: &str) -> Format {
+        match Path::new(path).extension().and_then(|extension| extension.to_str()).map(str::to_lowercase).as_deref() {
+            Some("toml") => Format::Toml,
+            Some("json") => Format::Json,
+            _ => Format::Ini,
+        }
+    }
+}
+
+/// The keys of a table in the order they appear in the file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table<T>(pub Vec<(String, T)>);
+
+impl<T> Table<T> {
+    pub fn get(&self, key: &str) -> Option<&T> {
+        self.0.iter().find(|(name, _)| name == key).map(|(_, value)| value)
+    }
+
+    /// Replaces the value of the key or appends it if the table does not contain it yet.
+    pub fn insert(&mut self, key: String, value: T) {
+        match self.0.iter_mut().find(|(name, _)| *name == key) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((key, value)),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Table<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TableVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for TableVisitor<T> {
+            type Value = Table<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a table")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut table = Table(vec![]);
+
+                while let Some((key, value)) = map.next_entry()? {
+                    table.0.push((key, value));
+                }
+
+                Ok(table)
+            }
+        }
+
+        deserializer.deserialize_map(TableVisitor(PhantomData))
+    }
+}
+
+/// A section of a TOML or JSON config with its typed values.
+pub type Section = Table<serde_json::Value>;
+
+impl Section {
+    /// Deserializes the section into the general config or a TSE entry.
+    pub fn parse<T: for<'de> Deserialize<'de>>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_value(serde_json::Value::Object(self.0.iter().cloned().collect()))
+    }
+}
+
+/// A config file, either the sections and keys of an INI file or a TOML or JSON document with a table per section like `[config]` or `[default]`.
+#[derive(Debug)]
+pub enum Document {
+    Ini(Ini),
+    Typed(Table<Section>),
+}
+
+impl Document {
+    /// The names of all sections in the order of the file.
+    pub fn section_names(&self) -> Vec<String> {
+        match self {
+            Document::Ini(ini) => ini.iter().map(|(name, _)| name.to_string()).collect(),
+            Document::Typed(sections) => sections.0.iter().map(|(name, _)| name.clone()).collect(),
+        }
+    }
+
+    /// The keys of all sections, INI values are strings while TOML and JSON values keep their type.
+    pub fn sections(&self) -> Vec<(String, Section)> {
+        match self {
+            Document::Ini(ini) => ini.iter().map(|(name, section)| (name.to_string(), Table(section.iter().map(|(key, value)| (key.to_string(), serde_json::Value::String(value.to_string()))).collect()))).collect(),
+            Document::Typed(sections) => sections.0.clone(),
+        }
+    }
+
+    /// Sets the key of a section to a value given as text, in TOML and JSON documents it is read as TOML value like `5000` or `true` if it is one.
+    pub fn set(self, section: String, key: String, value: String) -> Document {
+        match self {
+            Document::Ini(ini) => Document::Ini(ini.section(section).item(key, value)),
+            Document::Typed(mut sections) => {
+                let value = toml::from_str::<BTreeMap<String, serde_json::Value>>(&format!("value = {}", value)).ok().and_then(|mut parsed| parsed.remove("value")).unwrap_or(serde_json::Value::String(value));
+
+                match sections.0.iter_mut().find(|(name, _)| *name == section) {
+                    Some((_, keys)) => keys.insert(key, value),
+                    None => sections.0.push((section, Table(vec![(key, value)]))),
+                }
+
+                Document::Typed(sections)
+            }
+        }
+    }
+}
+
+/// Parses a config in the given format.
+pub fn parse(format: Format, text: &str) -> Result<Document, String> {
+    match format {
+        Format::Ini => Ini::from_string(text).map(Document::Ini).map_err(|err| err.to_string()),
+        Format::Toml => toml::from_str(text).map(Document::Typed).map_err(|err| err.to_string()),
+        Format::Json => serde_json::from_str(text).map(Document::Typed).map_err(|err| err.to_string()),
+    }
+}
+
+pub fn read(path: &str) -> Result<Document, String> {
+    let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+    parse(Format::of(path), &text)
+}
+
+/// A number or a text, e.g. a `tss_type` of `2` or `"2"`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Scalar {
+    Number(u64),
+    Text(String),
+}
+
+impl<'de> Deserialize<'de> for TssType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tss_type = match Scalar::deserialize(deserializer)? {
+            Scalar::Number(number) => number.to_string(),
+            Scalar::Text(text) => text,
+        };
+
+        match tss_type.as_str() {
+            "1" => Ok(TssType::AsignOnline),
+            "2" => Ok(TssType::CryptoVision),
+            _ => Err(de::Error::custom(format!("unknown tss_type {}, expected 1 or 2", tss_type))),
+        }
+    }
+}
+
+/// A list or a comma separated text like the lists of an INI file.
+pub(super) fn list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum List {
+        Items(Vec<String>),
+        Text(String),
+    }
+
+    let items = match List::deserialize(deserializer)? {
+        List::Items(items) => items,
+        List::Text(text) => text.split(',').map(String::from).collect(),
+    };
+
+    Ok(items.iter().map(|item| item.trim()).filter(|item| !item.is_empty()).map(String::from).collect())
+}
+
+/// An optional text, empty texts count as not set.
+pub(super) fn non_empty<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.filter(|text| !text.is_empty()))
+}
+
+/// An optional number, `0` counts as not set.
+pub(super) fn non_zero<'de, D: Deserializer<'de>, T: Deserialize<'de> + Default + PartialEq>(deserializer: D) -> Result<Option<T>, D::Error> {
+    Ok(Option::<T>::deserialize(deserializer)?.filter(|number| *number != T::default()))
+}
+
+/// A TSE entry of a TOML or JSON config with the same keys as an INI section.
+#[derive(Deserialize)]
+pub(super) struct Entry {
+    #[serde(default)]
+    name: String,
+    tss_type: TssType,
+    #[serde(deserialize_with = "list")]
+    scu_url: Vec<String>,
+    #[serde(default, deserialize_with = "list")]
+    scu_url_fallback: Vec<String>,
+    atrust_vtss_id: Option<String>,
+    atrust_api_key: Option<Secret>,
+    time_admin_id: Option<String>,
+    time_admin_pwd: Option<Secret>,
+    http_proxy: Option<String>,
+    http_proxy_username: Option<String>,
+    http_proxy_password: Option<Secret>,
+    timeout: Option<u64>,
+    retries: Option<u64>,
+    tls_ca_file: Option<String>,
+    tls_client_cert: Option<String>,
+    tls_client_key: Option<String>,
+    tls_client_cert_password: Option<Secret>,
+    #[serde(default, deserialize_with = "list")]
+    tls_pin_sha256: Vec<String>,
+    #[serde(default)]
+    tls_accept_invalid_certs: bool,
+    auth_type: Option<String>,
+    auth_header: Option<String>,
+    auth_api_key: Option<Secret>,
+    auth_token: Option<Secret>,
+    auth_username: Option<String>,
+    auth_password: Option<Secret>,
+    /// `http_header_<name>` keys, other unknown keys are reported by the validation.
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_json::Value>,
+}
+
+/// The name of an entry is left empty if it has no `name` key, the caller names it after its section.
+impl From<Entry> for Config {
+    fn from(entry: Entry) -> Config {
+        let mut scu_urls = entry.scu_url.into_iter().chain(entry.scu_url_fallback);
+
+        Config {
+            name: entry.name,
+            tss_type: entry.tss_type,
+            scu_url: scu_urls.next().unwrap_or_default(),
+            scu_url_fallbacks: scu_urls.collect(),
+            atrust_vtss_id: entry.atrust_vtss_id,
+            atrust_api_key: entry.atrust_api_key,
+            time_admin_id: entry.time_admin_id,
+            time_admin_pwd: entry.time_admin_pwd,
+            http: HttpConfig {
+                http_proxy: entry.http_proxy,
+                http_proxy_username: entry.http_proxy_username,
+                http_proxy_password: entry.http_proxy_password,
+                timeout: entry.timeout,
+                retries: entry.retries,
+            },
+            tls: TlsConfig {
+                ca_file: entry.tls_ca_file,
+                client_cert: entry.tls_client_cert,
+                client_key: entry.tls_client_key,
+                client_cert_password: entry.tls_client_cert_password,
+                pin_sha256: entry.tls_pin_sha256,
+                accept_invalid_certs: entry.tls_accept_invalid_certs,
+            },
+            auth: AuthConfig {
+                auth_type: entry.auth_type,
+                header: entry.auth_header,
+                api_key: entry.auth_api_key,
+                token: entry.auth_token,
+                username: entry.auth_username,
+                password: entry.auth_password,
+                headers: entry
+                    .other
+                    .into_iter()
+                    .filter_map(|(key, value)| match (key.strip_prefix("http_header_"), value) {
+                        (Some(name), serde_json::Value::String(value)) => Some((name.to_string(), value)),
+                        _ => None,
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
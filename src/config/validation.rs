@@ -0,0 +1,256 @@
+use std::{collections::HashMap, fmt, fs};
+
+use super::format::{self, Document, Format};
+
+/// What a config key accepts.
+#[derive(Clone, Copy)]
+enum Value {
+    Text,
+    /// A comma separated list of texts.
+    Texts,
+    Bool,
+    /// A number up to the given maximum.
+    Number(u64),
+    Url,
+    /// A comma separated list of urls.
+    Urls,
+    OneOf(&'static [&'static str]),
+    /// A comma separated list of the given values.
+    ListOf(&'static [&'static str]),
+}
+
+const TSS_TYPES: &[&str] = &["1", "2"];
+
+/// The keys of the `[config]` section.
+const GENERAL_KEYS: &[(&str, Value)] = &[
+    ("http_proxy", Value::Url),
+    ("http_proxy_username", Value::Text),
+    ("http_proxy_password", Value::Text),
+    ("timeout", Value::Number(u64::MAX)),
+    ("retries", Value::Number(u64::MAX)),
+    ("logging_enabled", Value::Bool),
+    ("logging_stderr", Value::Bool),
+    ("logging_file", Value::Bool),
+    ("log_dir", Value::Text),
+    ("log_level", Value::Text),
+    ("log_append", Value::Bool),
+    ("log_colors", Value::Bool),
+    ("log_details", Value::Bool),
+    ("log_stderr_colors", Value::Bool),
+    ("log_bodies", Value::Bool),
+    ("log_format", Value::OneOf(&["text", "json"])),
+    ("log_target", Value::ListOf(&["syslog", "journald"])),
+    ("log_facility", Value::Text),
+    ("log_identifier", Value::Text),
+    ("log_syslog_socket", Value::Text),
+    ("log_journald_socket", Value::Text),
+    ("log_rotate_size", Value::Number(u64::MAX)),
+    ("log_rotate_age", Value::OneOf(&["day", "hour", "minute", "second"])),
+    ("log_keep_files", Value::Number(u32::MAX as u64)),
+    ("log_compress", Value::Bool),
+    ("msg_upload_interval", Value::Number(u64::MAX)),
+    ("heartbeat_url", Value::Url),
+    ("max_audit_log_size", Value::Number(u32::MAX as u64)),
+    ("tse_info_cache_ttl", Value::Number(u64::MAX)),
+    ("scu_failover_cooldown", Value::Number(u64::MAX)),
+    ("circuit_breaker_threshold", Value::Number(u32::MAX as u64)),
+    ("circuit_breaker_reset", Value::Number(u64::MAX)),
+    ("strict_config", Value::Bool),
+    ("config_reload_interval", Value::Number(u64::MAX)),
+];
+
+/// The keys of a TSE entry, additionally any number of `http_header_<name>` keys are allowed.
+const TSE_KEYS: &[(&str, Value)] = &[
+    ("name", Value::Text),
+    ("tss_type", Value::OneOf(TSS_TYPES)),
+    ("scu_url", Value::Urls),
+    ("scu_url_fallback", Value::Urls),
+    ("atrust_vtss_id", Value::Text),
+    ("atrust_api_key", Value::Text),
+    ("time_admin_id", Value::Text),
+    ("time_admin_pwd", Value::Text),
+    ("http_proxy", Value::Url),
+    ("http_proxy_username", Value::Text),
+    ("http_proxy_password", Value::Text),
+    ("timeout", Value::Number(u64::MAX)),
+    ("retries", Value::Number(u64::MAX)),
+    ("tls_ca_file", Value::Text),
+    ("tls_client_cert", Value::Text),
+    ("tls_client_key", Value::Text),
+    ("tls_client_cert_password", Value::Text),
+    ("tls_pin_sha256", Value::Texts),
+    ("tls_accept_invalid_certs", Value::Bool),
+    ("auth_type", Value::OneOf(&["none", "api_key", "bearer", "basic"])),
+    ("auth_header", Value::Text),
+    ("auth_api_key", Value::Text),
+    ("auth_token", Value::Text),
+    ("auth_username", Value::Text),
+    ("auth_password", Value::Text),
+];
+
+pub fn is_general_key(key: &str) -> bool {
+    GENERAL_KEYS.iter().any(|(name, _)| *name == key)
+}
+
+/// A problem found in the config, values are not included as they might be secrets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub section: String,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl Problem {
+    fn new(section: &str, key: Option<&str>, message: impl Into<String>) -> Problem {
+        Problem {
+            section: section.to_string(),
+            key: key.map(String::from),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.section.as_str(), &self.key) {
+            ("", _) => write!(f, "{}", self.message),
+            (section, Some(key)) => write!(f, "[{}] {}: {}", section, key, self.message),
+            (section, None) => write!(f, "[{}] {}", section, self.message),
+        }
+    }
+}
+
+fn check_url(url: &str) -> Result<(), String> {
+    match reqwest::Url::parse(url) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(()),
+        Ok(url) => Err(format!("unsupported url scheme {}, only http and https are supported", url.scheme())),
+        Err(err) => Err(format!("invalid url: {}", err)),
+    }
+}
+
+fn check_value(value: &str, kind: Value) -> Result<(), String> {
+    let list = |value: &str| value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(String::from).collect::<Vec<String>>();
+
+    match kind {
+        Value::Text | Value::Texts => Ok(()),
+        Value::Bool => value.parse::<bool>().map(drop).map_err(|_| String::from("expected true or false")),
+        Value::Number(max) => match value.parse::<u64>() {
+            Ok(number) if number <= max => Ok(()),
+            _ if max == u64::MAX => Err(String::from("expected a number")),
+            _ => Err(format!("expected a number up to {}", max)),
+        },
+        Value::Url => check_url(value),
+        Value::Urls => list(value).iter().try_for_each(|url| check_url(url)),
+        Value::OneOf(allowed) if allowed.contains(&value) => Ok(()),
+        Value::OneOf(allowed) => Err(format!("unknown value {}, expected one of {}", value, allowed.join(", "))),
+        Value::ListOf(allowed) => list(value).iter().try_for_each(|item| check_value(item, Value::OneOf(allowed))),
+    }
+}
+
+/// Checks a value of a TOML or JSON config, unlike an INI value it has to have the type of the key, lists may also be given as comma separated text.
+fn check_typed(value: &serde_json::Value, kind: Value) -> Result<(), String> {
+    use serde_json::Value as Json;
+
+    match (value, kind) {
+        (Json::Bool(value), Value::Bool) => check_value(&value.to_string(), kind),
+        (Json::Number(number), Value::Number(_) | Value::OneOf(_)) => check_value(&number.to_string(), kind),
+        (Json::String(text), Value::Text | Value::Texts | Value::Url | Value::Urls | Value::OneOf(_) | Value::ListOf(_)) => check_value(text.trim(), kind),
+        (Json::Array(items), Value::Texts | Value::Urls | Value::ListOf(_)) => items.iter().try_for_each(|item| match item {
+            Json::String(item) => check_value(item.trim(), kind),
+            _ => Err(String::from("expected a list of texts")),
+        }),
+        (_, Value::Bool) => Err(String::from("expected true or false")),
+        (_, Value::Number(_)) => Err(String::from("expected a number")),
+        (_, Value::Texts | Value::Urls | Value::ListOf(_)) => Err(String::from("expected a text or a list of texts")),
+        _ => Err(String::from("expected a text")),
+    }
+}
+
+/// Section headers which appear more than once, their keys are merged silently.
+fn duplicate_sections(text: &str) -> Vec<Problem> {
+    let mut seen = vec![];
+    let mut problems = vec![];
+
+    for line in text.lines().map(str::trim) {
+        if let Some(section) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')).map(str::trim) {
+            if seen.contains(&section) {
+                problems.push(Problem::new(section, None, "section appears more than once, its keys are merged"));
+            } else {
+                seen.push(section);
+            }
+        }
+    }
+
+    problems
+}
+
+/// Checks the config file at `path` and the config `document` read from it and the environment. All problems are collected, the config is used anyway unless `strict_config` is set.
+pub fn validate(path: &str, document: Option<&Document>) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    if let Ok(text) = fs::read_to_string(path) {
+        match format::parse(Format::of(path), &text) {
+            Ok(_) if Format::of(path) == Format::Ini => problems.extend(duplicate_sections(&text)),
+            Ok(_) => {}
+            Err(err) => problems.push(Problem::new("", None, format!("could not parse {}: {}", path, err))),
+        }
+    }
+
+    let document = match document {
+        Some(document) => document,
+        None => {
+            problems.push(Problem::new("", None, format!("no config found at {}", path)));
+            return problems;
+        }
+    };
+
+    let check = |value: &serde_json::Value, kind: Value| match (document, value) {
+        (Document::Ini(_), serde_json::Value::String(value)) => check_value(value.trim(), kind),
+        _ => check_typed(value, kind),
+    };
+
+    let sections = document.sections();
+    let mut names: HashMap<String, String> = HashMap::new();
+
+    for (section_name, section) in sections.iter() {
+        let known = if section_name == "config" { GENERAL_KEYS } else { TSE_KEYS };
+
+        for (key, value) in section.0.iter() {
+            match known.iter().find(|(name, _)| name == key) {
+                Some((_, kind)) => {
+                    if let Err(message) = check(value, *kind) {
+                        problems.push(Problem::new(section_name, Some(key), message));
+                    }
+                }
+                None if section_name != "config" && key.starts_with("http_header_") => {
+                    if let Err(message) = check(value, Value::Text) {
+                        problems.push(Problem::new(section_name, Some(key), message));
+                    }
+                }
+                None => problems.push(Problem::new(section_name, Some(key), "unknown key")),
+            }
+        }
+
+        if section_name == "config" {
+            continue;
+        }
+
+        for required in ["tss_type", "scu_url"] {
+            if section.get(required).is_none() {
+                problems.push(Problem::new(section_name, Some(required), "missing, the entry is ignored"));
+            }
+        }
+
+        let name = section.get("name").and_then(|name| name.as_str()).map(String::from).unwrap_or_else(|| section_name.to_string());
+        if let Some(other) = names.insert(name.clone(), section_name.to_string()) {
+            problems.push(Problem::new(section_name, Some("name"), format!("{} is already used by [{}]", name, other)));
+        }
+    }
+
+    let default = super::default_section(document);
+    if default == "config" || !sections.iter().any(|(name, section)| *name == default && !section.0.is_empty()) {
+        problems.push(Problem::new("", None, format!("no [{}] entry, functions without a TSE id can not be used", default)));
+    }
+
+    problems
+}
@@ -0,0 +1,181 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::{backend, client, config, idesscd::*, logging};
+
+/// Share of the log memory or of the startable transactions of a TSE from which on a warning is logged.
+const USAGE_WARNING: f64 = 0.9;
+
+/// Outcome of the last check of a TSE.
+#[derive(Debug, Clone, Serialize)]
+pub struct Health {
+    pub tse_id: String,
+    pub reachable: bool,
+    pub checked_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_transactions: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_open_transactions: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_memory_size: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_log_memory_size: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct Heartbeat<'a> {
+    timestamp: String,
+    tses: Vec<&'a Health>,
+}
+
+struct Worker {
+    stop: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+static WORKER: Lazy<Mutex<Option<Worker>>> = Lazy::new(|| Mutex::new(None));
+
+static HEALTH: Lazy<Mutex<HashMap<String, Health>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Starts checking all configured TSEs every `msg_upload_interval` seconds, `0` disables the heartbeat.
+pub fn start() {
+    let interval = config::GENERAL_CONFIG.lock().map(|general_config| general_config.msg_upload_interval).unwrap_or(config::DEFAULT_MSG_UPLOAD_INTERVAL);
+    let mut worker = ok_or_return!(WORKER.lock(), |_| ());
+
+    if worker.is_some() || interval == 0 {
+        return;
+    }
+
+    let (stop, stopped) = mpsc::channel();
+
+    let handle = thread::Builder::new().name(String::from("heartbeat")).spawn(move || {
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(Duration::from_secs(interval)) {
+            beat();
+        }
+    });
+
+    match handle {
+        Ok(handle) => *worker = Some(Worker { stop, handle }),
+        Err(err) => log::error!("could not start the heartbeat: {}", err),
+    }
+}
+
+/// Stops the heartbeat and waits for a running check to finish.
+pub fn stop() {
+    let worker = ok_or_return!(WORKER.lock(), |_| ()).take();
+
+    if let Some(worker) = worker {
+        let _ = worker.stop.send(());
+        let _ = worker.handle.join();
+    }
+}
+
+/// Restarts a running heartbeat so a changed `msg_upload_interval` takes effect.
+pub fn reschedule() {
+    if ok_or_return!(WORKER.lock(), |_| ()).is_none() {
+        return;
+    }
+
+    stop();
+    start();
+}
+
+/// The health of all TSEs recorded by the last heartbeat.
+pub fn health() -> Vec<Health> {
+    let mut health: Vec<Health> = ok_or_return!(HEALTH.lock(), |_| vec![]).values().cloned().collect();
+    health.sort_by(|a, b| a.tse_id.cmp(&b.tse_id));
+
+    health
+}
+
+/// Forgets the recorded health of all TSEs.
+pub fn clear() {
+    if let Ok(mut health) = HEALTH.lock() {
+        health.clear();
+    }
+}
+
+fn check(tse_id: &str) -> Health {
+    let mut health = Health {
+        tse_id: tse_id.to_string(),
+        reachable: false,
+        checked_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        error: None,
+        open_transactions: None,
+        max_open_transactions: None,
+        log_memory_size: None,
+        max_log_memory_size: None,
+    };
+
+    let tse_info = backend::get(tse_id.to_string()).and_then(|backend| {
+        backend.echo(&ScuDeEchoRequest { message: String::from("heartbeat") })?;
+        backend.get_tse_info()
+    });
+
+    match tse_info {
+        Ok(tse_info) => {
+            health.reachable = true;
+            health.open_transactions = Some(tse_info.current_number_of_started_transactions);
+            health.max_open_transactions = Some(tse_info.max_number_of_started_transactions);
+            health.log_memory_size = Some(tse_info.current_log_memory_size);
+            health.max_log_memory_size = Some(tse_info.max_log_memory_size);
+
+            if tse_info.max_log_memory_size > 0 && tse_info.current_log_memory_size as f64 >= tse_info.max_log_memory_size as f64 * USAGE_WARNING {
+                log::warn!("log memory of {} is nearly full, {} of {} bytes used", tse_id, tse_info.current_log_memory_size, tse_info.max_log_memory_size);
+            }
+
+            if tse_info.max_number_of_started_transactions > 0 && tse_info.current_number_of_started_transactions as f64 >= tse_info.max_number_of_started_transactions as f64 * USAGE_WARNING {
+                log::warn!("{} has {} of {} possible transactions open", tse_id, tse_info.current_number_of_started_transactions, tse_info.max_number_of_started_transactions);
+            }
+        }
+        Err(err) => {
+            log::warn!("heartbeat of {} failed: {}", tse_id, err);
+            health.error = Some(err.to_string());
+        }
+    }
+
+    health
+}
+
+/// Checks every configured TSE and posts the result to `heartbeat_url` if one is configured.
+fn beat() {
+    let _correlation = logging::Correlation::start();
+
+    for config in config::get_all_tss() {
+        let health = check(&config.name);
+
+        if let Ok(mut recorded) = HEALTH.lock() {
+            recorded.insert(config.name, health);
+        }
+    }
+
+    let general_config = ok_or_return!(config::GENERAL_CONFIG.lock(), |_| ()).clone();
+
+    if let Some(heartbeat_url) = general_config.heartbeat_url {
+        let health = health();
+        let heartbeat = Heartbeat {
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            tses: health.iter().collect(),
+        };
+
+        let client = ok_or_return!(client::general_http_client(), |err| log::warn!("could not send the heartbeat to {}: {}", heartbeat_url, err));
+
+        match client.post(&heartbeat_url).header(client::CORRELATION_ID_HEADER, logging::correlation_id().unwrap_or_default()).json(&heartbeat).send() {
+            Ok(response) if response.status().is_success() => log::debug!("heartbeat sent to {}", heartbeat_url),
+            Ok(response) => log::warn!("heartbeat to {} returned {}", heartbeat_url, response.status()),
+            Err(err) => log::warn!("could not send the heartbeat to {}: {}", heartbeat_url, err),
+        }
+    }
+}
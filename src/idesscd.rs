@@ -12,9 +12,17 @@ use uuid::Uuid;
 #[cfg(feature = "mocks")]
 use crate::helpers::fakers::*;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Base64 encoded process data, signatures and certificates.
+/// Its `Debug` implementation only prints the length and hash of the content to keep personal data out of the logs.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Base64(String);
 
+impl std::fmt::Debug for Base64 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Base64(len: {}, sha256: {})", self.0.len(), sha256::digest(self.0.as_str()))
+    }
+}
+
 impl Base64 {
     pub fn from<T>(from: T) -> Self
     where
@@ -117,7 +125,7 @@ pub struct FinishTransactionResponse {
     pub signature_data: TseSignatureData,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "mocks", derive(Dummy))]
 #[serde(rename_all = "PascalCase")]
 pub struct TseInfo {
@@ -141,7 +149,7 @@ pub struct TseInfo {
     pub certificates_base64: Vec<Base64>,
 }
 
-#[derive(Debug, Serialize_repr, Deserialize_repr)]
+#[derive(Debug, Clone, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 #[cfg_attr(feature = "mocks", derive(Dummy))]
 pub enum TseStates {
@@ -228,7 +236,7 @@ pub struct ExportDataRequest {
 #[serde(rename_all = "PascalCase")]
 pub struct ExportDataResponse {
     pub token_id: String,
-    pub tar_file_byte_chunk_base64: String,
+    pub tar_file_byte_chunk_base64: Base64,
     #[cfg_attr(feature = "mocks", dummy(faker = "fake::faker::boolean::en::Boolean(5)"))]
     pub tar_file_end_of_file: bool,
     pub total_tar_file_size_available: bool,
@@ -266,6 +274,13 @@ pub struct ScuDeEchoResponse {
     pub message: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "mocks", derive(Dummy))]
+#[serde(rename_all = "PascalCase")]
+pub struct ScuDeVersion {
+    pub version: String,
+}
+
 #[cfg(feature = "mocks")]
 #[derive(Debug, thiserror::Error)]
 pub enum Error {}
@@ -289,4 +304,5 @@ pub trait IDeSscd {
     fn export_data(&self, request: &ExportDataRequest) -> Result<ExportDataResponse, Self::Error>;
     fn end_export_session(&self, request: &EndExportSessionRequest) -> Result<EndExportSessionResponse, Self::Error>;
     fn echo(&self, request: &ScuDeEchoRequest) -> Result<ScuDeEchoResponse, Self::Error>;
+    fn get_version(&self) -> Result<ScuDeVersion, Self::Error>;
 }
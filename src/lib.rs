@@ -5,7 +5,12 @@ pub mod helpers;
 
 pub mod atrustapi;
 
+mod audit;
+mod backend;
 mod client;
 mod config;
+mod heartbeat;
 pub mod idesscd;
+mod lifecycle;
 mod logging;
+mod reload;
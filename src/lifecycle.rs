@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{atrustapi::asigntse, backend, client, config, heartbeat, logging, reload};
+
+/// Set by `at_load` and cleared by `at_unload`, no TSE can be used while it is unset.
+static LOADED: AtomicBool = AtomicBool::new(false);
+
+pub fn is_loaded() -> bool {
+    LOADED.load(Ordering::SeqCst)
+}
+
+/// Validates the config, builds the backends of all TSE entries and starts the background workers.
+/// Entries which can not be used are logged and keep failing with their error, loading only fails if no entry is usable.
+pub fn load() -> Result<(), backend::Error> {
+    if !config::check() {
+        return Err(backend::Error::InvalidConfig(String::from("the config has problems and strict_config is set")));
+    }
+
+    LOADED.store(true, Ordering::SeqCst);
+
+    let mut usable = 0;
+    for config in config::get_all_tss() {
+        match backend::get(config.name.clone()) {
+            Ok(_) => usable += 1,
+            Err(err) => log::error!("config entry {} can not be used: {}", config.name, err),
+        }
+    }
+
+    if usable == 0 {
+        LOADED.store(false, Ordering::SeqCst);
+        return Err(backend::Error::InvalidConfig(String::from("no usable tse entry")));
+    }
+
+    heartbeat::start();
+    reload::start();
+
+    Ok(())
+}
+
+/// Stops the background workers, drops all backends and their connections and forgets everything learned about the SCUs and the admin credentials, so the next `at_load` starts clean.
+/// The config is kept.
+pub fn unload() {
+    reload::stop();
+    heartbeat::stop();
+
+    LOADED.store(false, Ordering::SeqCst);
+
+    backend::clear();
+    client::reset();
+    heartbeat::clear();
+    asigntse::reset_admin_credentials();
+
+    logging::flush();
+}
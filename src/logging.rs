@@ -1,8 +1,170 @@
-use flexi_logger::{colored_default_format, colored_detailed_format, default_format, detailed_format, FileSpec, LogSpecification, Logger};
+use std::{cell::RefCell, convert::TryFrom, sync::RwLock};
+
+use flexi_logger::{colored_default_format, colored_detailed_format, default_format, detailed_format, writers::FileLogWriter, Age, Cleanup, Criterion, FileSpec, FormatFunction, LogSpecification, Logger, Naming};
 use once_cell::sync::OnceCell;
 
+use crate::{atrustapi::return_codes::ReturnCode, config::GeneralConfig};
+
+#[cfg(unix)]
+mod syslog;
+
 static LOGGER: OnceCell<flexi_logger::LoggerHandle> = OnceCell::new();
 
+thread_local! {
+    static CORRELATION_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+    static CALL: RefCell<Option<CallContext>> = const { RefCell::new(None) };
+}
+
+/// The exported function running on this thread, the TSE it works on and the return code it will return.
+#[derive(Clone)]
+struct CallContext {
+    function: &'static str,
+    tse_id: Option<String>,
+    return_code: Option<ReturnCode>,
+}
+
+/// Returns the correlation id of the operation running on this thread.
+pub fn correlation_id() -> Option<String> {
+    CORRELATION_ID.with(|correlation_id| correlation_id.borrow().clone())
+}
+
+/// Marks all log lines of this thread with the correlation id of the current operation until it is dropped.
+pub struct Correlation {
+    previous: Option<String>,
+}
+
+impl Correlation {
+    /// Starts a new operation, nested operations keep the correlation id of the outer one.
+    pub fn start() -> Correlation {
+        let previous = correlation_id();
+        let correlation_id = previous.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        CORRELATION_ID.with(|current| *current.borrow_mut() = Some(correlation_id));
+
+        Correlation { previous }
+    }
+}
+
+impl Drop for Correlation {
+    fn drop(&mut self) {
+        CORRELATION_ID.with(|current| *current.borrow_mut() = self.previous.take());
+    }
+}
+
+/// Runs the body of the exported function `function` working on the TSE `tse_id` and marks all log lines it writes with them, its correlation id and the return code once it returned.
+/// Exported functions calling each other, like the variants without TSE id calling their `WithTse` variant, keep the context of the outermost one.
+pub fn call(function: &'static str, tse_id: Option<&str>, body: impl FnOnce() -> i32) -> i32 {
+    let call = match Call::start(function, tse_id) {
+        Some(call) => call,
+        None => return body(),
+    };
+
+    let return_code = body();
+    call.returned(return_code);
+
+    return_code
+}
+
+/// The context of the running exported function, it is logged and cleared once the function returned.
+struct Call {
+    _correlation: Correlation,
+}
+
+impl Call {
+    /// Returns `None` if an exported function is already running on this thread.
+    fn start(function: &'static str, tse_id: Option<&str>) -> Option<Call> {
+        let outermost = CALL.with(|call| {
+            let mut call = call.borrow_mut();
+
+            if call.is_some() {
+                return false;
+            }
+
+            *call = Some(CallContext {
+                function,
+                tse_id: tse_id.map(String::from),
+                return_code: None,
+            });
+            true
+        });
+
+        if !outermost {
+            return None;
+        }
+
+        let call = Call { _correlation: Correlation::start() };
+        log::info!("{}", function);
+
+        Some(call)
+    }
+
+    fn returned(&self, return_code: i32) {
+        CALL.with(|call| {
+            if let Some(call) = call.borrow_mut().as_mut() {
+                call.return_code = ReturnCode::try_from(return_code).ok();
+            }
+        });
+    }
+}
+
+impl Drop for Call {
+    fn drop(&mut self) {
+        if let Some(CallContext { function, return_code: Some(return_code), .. }) = call_context() {
+            log::debug!("{} returned {}", function, return_code);
+        }
+
+        CALL.with(|call| *call.borrow_mut() = None);
+    }
+}
+
+fn call_context() -> Option<CallContext> {
+    CALL.with(|call| call.borrow().clone())
+}
+
+/// Writes the log line as a JSON object with the context of the running exported function.
+fn json_format(w: &mut dyn std::io::Write, now: &mut flexi_logger::DeferredNow, record: &log::Record) -> Result<(), std::io::Error> {
+    let call = call_context();
+    let timestamp = chrono::DateTime::<chrono::Utc>::from(std::time::SystemTime::from(*now.now()));
+
+    let line = serde_json::json!({
+        "timestamp": timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        "level": record.level().as_str(),
+        "module": record.module_path(),
+        "tse_id": call.as_ref().and_then(|call| call.tse_id.clone()),
+        "function": call.as_ref().map(|call| call.function),
+        "correlation_id": correlation_id(),
+        "return_code": call.as_ref().and_then(|call| call.return_code).map(i32::from),
+        "message": record.args().to_string(),
+    });
+
+    write!(w, "{}", line)
+}
+
+/// Wraps a flexi_logger format so the message is prefixed with the correlation id of the current operation.
+macro_rules! correlated {
+    ($format:ident) => {{
+        fn format(w: &mut dyn std::io::Write, now: &mut flexi_logger::DeferredNow, record: &log::Record) -> Result<(), std::io::Error> {
+            match correlation_id() {
+                Some(correlation_id) => $format(
+                    w,
+                    now,
+                    &log::Record::builder()
+                        .args(format_args!("[{}] {}", correlation_id, record.args()))
+                        .level(record.level())
+                        .target(record.target())
+                        .module_path(record.module_path())
+                        .file(record.file())
+                        .line(record.line())
+                        .build(),
+                ),
+                None => $format(w, now, record),
+            }
+        }
+
+        format
+    }};
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("Lock for general config is poisoned")]
@@ -13,6 +175,141 @@ pub enum Error {
     Logger(#[from] flexi_logger::FlexiLoggerError),
     #[error("Logger already configured")]
     LoggerAlreadyConfigured,
+    #[error("Invalid log_format {0}, expected text or json")]
+    InvalidLogFormat(String),
+    #[error("Invalid log_target {0}, expected syslog or journald")]
+    InvalidLogTarget(String),
+    #[error("Invalid log_facility {0}")]
+    InvalidLogFacility(String),
+    #[error("Could not open the syslog socket: {0}")]
+    SystemLog(#[source] std::io::Error),
+    #[cfg(not(unix))]
+    #[error("log_target {0} is only supported on unix")]
+    LogTargetNotSupported(String),
+    #[error("Invalid log_rotate_age {0}, expected day, hour, minute or second")]
+    InvalidLogRotateAge(String),
+}
+
+/// Maps the `log_rotate_size`, `log_rotate_age`, `log_keep_files` and `log_compress` settings to flexi_logger's rotation, `None` if the log file is not rotated.
+fn rotation(general_config: &GeneralConfig) -> Result<Option<(Criterion, Naming, Cleanup)>, Error> {
+    let age = match general_config.log_rotate_age.as_deref() {
+        None => None,
+        Some("day") => Some(Age::Day),
+        Some("hour") => Some(Age::Hour),
+        Some("minute") => Some(Age::Minute),
+        Some("second") => Some(Age::Second),
+        Some(age) => return Err(Error::InvalidLogRotateAge(age.to_string())),
+    };
+
+    let criterion = match (age, general_config.log_rotate_size) {
+        (Some(age), Some(size)) => Criterion::AgeOrSize(age, size),
+        (Some(age), None) => Criterion::Age(age),
+        (None, Some(size)) => Criterion::Size(size),
+        (None, None) => return Ok(None),
+    };
+
+    let cleanup = match (general_config.log_keep_files, general_config.log_compress) {
+        (keep_files, true) => Cleanup::KeepCompressedFiles(keep_files.unwrap_or(usize::MAX)),
+        (Some(keep_files), false) => Cleanup::KeepLogFiles(keep_files),
+        (None, false) => Cleanup::Never,
+    };
+
+    Ok(Some((criterion, Naming::Numbers, cleanup)))
+}
+
+/// Builds the writer for the `syslog` and `journald` entries of `log_target`, `None` if there are none.
+#[cfg(unix)]
+fn system_log(general_config: &GeneralConfig) -> Result<Option<Box<dyn flexi_logger::writers::LogWriter>>, Error> {
+    if general_config.log_target.is_empty() {
+        return Ok(None);
+    }
+
+    let targets = general_config
+        .log_target
+        .iter()
+        .map(|target| match target.as_str() {
+            "syslog" => Ok((syslog::Protocol::Syslog, general_config.log_syslog_socket.as_deref().unwrap_or(syslog::DEFAULT_SYSLOG_SOCKET).into())),
+            "journald" => Ok((syslog::Protocol::Journald, general_config.log_journald_socket.as_deref().unwrap_or(syslog::DEFAULT_JOURNALD_SOCKET).into())),
+            target => Err(Error::InvalidLogTarget(target.to_string())),
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let facility = syslog::facility(general_config.log_facility.as_deref().unwrap_or("user"))?;
+    let identifier = general_config.log_identifier.clone().unwrap_or_else(|| String::from(syslog::DEFAULT_IDENTIFIER));
+
+    Ok(Some(Box::new(syslog::SyslogWriter::new(targets, facility, identifier)?)))
+}
+
+#[cfg(not(unix))]
+fn system_log(general_config: &GeneralConfig) -> Result<Option<Box<dyn flexi_logger::writers::LogWriter>>, Error> {
+    match general_config.log_target.first() {
+        Some(target) => Err(Error::LogTargetNotSupported(target.clone())),
+        None => Ok(None),
+    }
+}
+
+fn is_json(general_config: &GeneralConfig) -> Result<bool, Error> {
+    match general_config.log_format.as_deref() {
+        None | Some("text") => Ok(false),
+        Some("json") => Ok(true),
+        Some(log_format) => Err(Error::InvalidLogFormat(log_format.to_string())),
+    }
+}
+
+fn format(general_config: &GeneralConfig) -> Result<FormatFunction, Error> {
+    if is_json(general_config)? {
+        return Ok(json_format);
+    }
+
+    Ok(match (general_config.log_colors, general_config.log_details) {
+        (true, true) => correlated!(colored_detailed_format),
+        (true, false) => correlated!(colored_default_format),
+        (false, true) => correlated!(detailed_format),
+        (false, false) => correlated!(default_format),
+    })
+}
+
+/// The format of the log lines, flexi_logger keeps the format a file log writer was created with so it is swapped here on reconfiguration.
+static FORMAT: RwLock<FormatFunction> = RwLock::new(default_format);
+
+fn current_format(w: &mut dyn std::io::Write, now: &mut flexi_logger::DeferredNow, record: &log::Record) -> Result<(), std::io::Error> {
+    let format = *FORMAT.read().unwrap_or_else(|err| err.into_inner());
+
+    format(w, now, record)
+}
+
+fn set_format(general_config: &GeneralConfig) -> Result<(), Error> {
+    *FORMAT.write().map_err(|_| Error::LockingConfig)? = format(general_config)?;
+
+    Ok(())
+}
+
+/// Writes out everything still buffered by the log writers.
+pub fn flush() {
+    if let Some(logger) = LOGGER.get() {
+        logger.flush();
+    }
+}
+
+/// Applies changed log file settings to the running logger, e.g. after one of the `cfgSetLog*` setters was called.
+/// A new log file is started, the logger is configured from scratch if it was not yet running.
+pub fn reconfigure_logging() -> Result<(), Error> {
+    let logger = match LOGGER.get() {
+        Some(logger) => logger,
+        None => return configure_logging(),
+    };
+
+    let general_config = crate::config::GENERAL_CONFIG.lock().map_err(|_| Error::LockingConfig)?.clone();
+
+    set_format(&general_config)?;
+
+    if !general_config.logging_file {
+        return Ok(());
+    }
+
+    let file_log_writer = FileLogWriter::builder(FileSpec::default().directory(&general_config.log_dir)).format(current_format).o_rotate(rotation(&general_config)?).o_append(general_config.log_append);
+
+    Ok(logger.reset_flw(&file_log_writer)?)
 }
 
 pub fn configure_logging() -> Result<(), Error> {
@@ -29,32 +326,35 @@ pub fn configure_logging() -> Result<(), Error> {
 
         let mut logger_builder = Logger::with(log_spec).o_append(general_config.log_append);
 
-        if !general_config.logging_file && !general_config.logging_stderr {
+        let system_log = system_log(&general_config)?;
+
+        if !general_config.logging_file && !general_config.logging_stderr && system_log.is_none() {
             logger_builder = logger_builder.do_not_log();
-        } else if general_config.logging_file {
+        } else if general_config.logging_file || system_log.is_some() {
             let file_spec = FileSpec::default().directory(&general_config.log_dir);
 
-            logger_builder = logger_builder.log_to_file(file_spec);
+            logger_builder = match (general_config.logging_file, system_log) {
+                (true, Some(system_log)) => logger_builder.log_to_file_and_writer(file_spec, system_log),
+                (true, None) => logger_builder.log_to_file(file_spec),
+                (false, Some(system_log)) => logger_builder.log_to_writer(system_log),
+                (false, None) => logger_builder,
+            }
+            .o_rotate(rotation(&general_config)?);
 
             if general_config.logging_stderr {
                 logger_builder = logger_builder.duplicate_to_stderr(flexi_logger::Duplicate::All);
             }
         }
 
-        if general_config.log_colors && general_config.log_details {
-            logger_builder = logger_builder.format(colored_detailed_format);
-        } else if general_config.log_colors && !general_config.log_details {
-            logger_builder = logger_builder.format(colored_default_format);
-        } else if !general_config.log_colors && general_config.log_details {
-            logger_builder = logger_builder.format(detailed_format);
-        } else if !general_config.log_colors && !general_config.log_details {
-            logger_builder = logger_builder.format(default_format);
-        }
+        set_format(&general_config)?;
+        logger_builder = logger_builder.format(current_format);
 
-        if general_config.log_stderr_colors && general_config.log_details {
-            logger_builder = logger_builder.format_for_stderr(colored_detailed_format);
+        if is_json(&general_config)? {
+            logger_builder = logger_builder.format_for_stderr(json_format);
+        } else if general_config.log_stderr_colors && general_config.log_details {
+            logger_builder = logger_builder.format_for_stderr(correlated!(colored_detailed_format));
         } else if general_config.log_stderr_colors && !general_config.log_details {
-            logger_builder = logger_builder.format_for_stderr(colored_default_format);
+            logger_builder = logger_builder.format_for_stderr(correlated!(colored_default_format));
         }
 
         LOGGER.set(logger_builder.start()?).map_err(|_| Error::LoggerAlreadyConfigured)?;
@@ -0,0 +1,149 @@
+use std::{io, os::unix::net::UnixDatagram, path::PathBuf};
+
+use flexi_logger::{writers::LogWriter, DeferredNow};
+use log::{Level, Record};
+
+use super::{call_context, correlation_id, Error};
+
+pub const DEFAULT_SYSLOG_SOCKET: &str = "/dev/log";
+pub const DEFAULT_JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+pub const DEFAULT_IDENTIFIER: &str = "asigntseonline";
+
+const FACILITIES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+
+/// Parses a syslog facility name like `user` or `local0` into its code.
+pub fn facility(name: &str) -> Result<u8, Error> {
+    FACILITIES.iter().position(|facility| *facility == name).map(|code| code as u8).ok_or_else(|| Error::InvalidLogFacility(name.to_string()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// RFC 3164 messages as accepted by the local syslog daemon.
+    Syslog,
+    /// The native protocol of systemd-journald.
+    Journald,
+}
+
+/// Sends every log line as a datagram to the local syslog daemon and/or journald.
+/// The socket is non-blocking and lines are dropped while a daemon is unavailable, logging must never stall a fiscal operation.
+pub struct SyslogWriter {
+    targets: Vec<(Protocol, PathBuf)>,
+    socket: UnixDatagram,
+    facility: u8,
+    identifier: String,
+}
+
+impl SyslogWriter {
+    pub fn new(targets: Vec<(Protocol, PathBuf)>, facility: u8, identifier: String) -> Result<SyslogWriter, Error> {
+        let socket = UnixDatagram::unbound().map_err(Error::SystemLog)?;
+        socket.set_nonblocking(true).map_err(Error::SystemLog)?;
+
+        Ok(SyslogWriter { targets, socket, facility, identifier })
+    }
+
+    fn message(record: &Record) -> String {
+        match correlation_id() {
+            Some(correlation_id) => format!("[{}] {}", correlation_id, record.args()),
+            None => record.args().to_string(),
+        }
+    }
+
+    fn syslog(&self, record: &Record) -> Vec<u8> {
+        let priority = self.facility as u32 * 8 + severity(record.level()) as u32;
+        let timestamp = chrono::Local::now().format("%b %e %H:%M:%S");
+
+        format!("<{}>{} {}[{}]: {}", priority, timestamp, self.identifier, std::process::id(), Self::message(record)).into_bytes()
+    }
+
+    fn journald(&self, record: &Record) -> Vec<u8> {
+        let mut datagram = vec![];
+
+        let mut field = |name: &str, value: &str| {
+            datagram.extend_from_slice(name.as_bytes());
+
+            if value.contains('\n') {
+                datagram.push(b'\n');
+                datagram.extend_from_slice(&(value.len() as u64).to_le_bytes());
+            } else {
+                datagram.push(b'=');
+            }
+
+            datagram.extend_from_slice(value.as_bytes());
+            datagram.push(b'\n');
+        };
+
+        field("MESSAGE", &record.args().to_string());
+        field("PRIORITY", &severity(record.level()).to_string());
+        field("SYSLOG_FACILITY", &self.facility.to_string());
+        field("SYSLOG_IDENTIFIER", &self.identifier);
+        field("SYSLOG_PID", &std::process::id().to_string());
+        field("CODE_MODULE", record.module_path().unwrap_or_default());
+
+        if let Some(correlation_id) = correlation_id() {
+            field("CORRELATION_ID", &correlation_id);
+        }
+
+        if let Some(call) = call_context() {
+            field("FUNCTION", call.function);
+
+            if let Some(tse_id) = &call.tse_id {
+                field("TSE_ID", tse_id);
+            }
+        }
+
+        datagram
+    }
+}
+
+fn severity(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+impl LogWriter for SyslogWriter {
+    fn write(&self, _now: &mut DeferredNow, record: &Record) -> io::Result<()> {
+        for (protocol, path) in &self.targets {
+            let datagram = match protocol {
+                Protocol::Syslog => self.syslog(record),
+                Protocol::Journald => self.journald(record),
+            };
+
+            let _ = self.socket.send_to(&datagram, path);
+        }
+
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    fs,
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime},
+};
+
+use once_cell::sync::Lazy;
+
+use crate::{backend, config, heartbeat, lifecycle, logging};
+
+struct Worker {
+    stop: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+static WORKER: Lazy<Mutex<Option<Worker>>> = Lazy::new(|| Mutex::new(None));
+
+fn interval() -> u64 {
+    config::GENERAL_CONFIG.lock().map(|general_config| general_config.config_reload_interval).unwrap_or(0)
+}
+
+/// Modification time and size of the config file, the file counts as changed if either differs.
+fn stamp() -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(&**config::CONFIG_FILE.load()).ok()?;
+
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Polls the config file every `config_reload_interval` seconds and reloads it once it changed, `0` disables the hot reload.
+pub fn start() {
+    let mut worker = ok_or_return!(WORKER.lock(), |_| ());
+
+    if worker.is_some() || interval() == 0 {
+        return;
+    }
+
+    let (stop, stopped) = mpsc::channel();
+    let mut seen = stamp();
+
+    let handle = thread::Builder::new().name(String::from("config-reload")).spawn(move || loop {
+        let interval = interval();
+
+        if interval == 0 {
+            log::info!("config_reload_interval is 0, stopped watching the config file");
+            return forget();
+        }
+
+        if stopped.recv_timeout(Duration::from_secs(interval)) != Err(RecvTimeoutError::Timeout) {
+            break;
+        }
+
+        // a rejected file is retried until it is fixed, it might have been read while being written
+        let current = stamp();
+        if current != seen && reload() {
+            seen = current;
+        }
+    });
+
+    match handle {
+        Ok(handle) => *worker = Some(Worker { stop, handle }),
+        Err(err) => log::error!("could not start watching the config file: {}", err),
+    }
+}
+
+/// Removes the worker of the current thread after it stopped on its own, so `start` can start a new one.
+fn forget() {
+    let mut worker = ok_or_return!(WORKER.lock(), |_| ());
+
+    if worker.as_ref().map(|worker| worker.handle.thread().id() == thread::current().id()).unwrap_or(false) {
+        *worker = None;
+    }
+}
+
+/// Stops watching the config file.
+pub fn stop() {
+    let worker = ok_or_return!(WORKER.lock(), |_| ()).take();
+
+    if let Some(worker) = worker {
+        let _ = worker.stop.send(());
+        let _ = worker.handle.join();
+    }
+}
+
+/// Watches the current config file with the current settings, e.g. after `cfgSetConfigFile`.
+pub fn restart() {
+    stop();
+
+    if lifecycle::is_loaded() {
+        start();
+    }
+}
+
+/// Reloads the changed config file, returns `false` if it was rejected and the previous config kept.
+fn reload() -> bool {
+    let _correlation = logging::Correlation::start();
+    let path = config::CONFIG_FILE.load_full();
+
+    let changes = some_or_return!(config::reload(), {
+        log::warn!("{} changed but is invalid, keeping the previous config", path);
+        false
+    });
+
+    if changes.is_empty() {
+        return true;
+    }
+
+    log::info!("reloaded {}", path);
+    for change in changes.iter() {
+        log::info!("config changed: {}", change);
+    }
+    config::log_effective();
+
+    backend::clear();
+
+    if changes.iter().any(|change| change.starts_with("[config]")) {
+        if let Err(err) = logging::reconfigure_logging() {
+            log::error!("{}", err);
+        }
+
+        heartbeat::reschedule();
+    }
+
+    true
+}
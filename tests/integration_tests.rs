@@ -1,4 +1,11 @@
-use std::convert::TryFrom;
+use std::{
+    convert::TryFrom,
+    io::{Read, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use chrono::{TimeZone, Utc};
 use fake::{Fake, Faker};
@@ -6,7 +13,7 @@ use middleware_wrapper_atrust::{atrustapi::return_codes::ReturnCode, helpers::ff
 use once_cell::sync::Lazy;
 use serial_test::serial;
 use wiremock::{
-    matchers::{method, path},
+    matchers::{header, method, path, path_regex},
     Mock, MockServer, Request, Respond, ResponseTemplate,
 };
 
@@ -36,6 +43,7 @@ static MOCK_IDESSCD: Lazy<MockIDeSscd> = Lazy::new(|| {
     mock_idesscd.expect_export_data().returning(|_| Ok(Faker.fake::<ExportDataResponse>()));
     mock_idesscd.expect_end_export_session().returning(|_| Ok(Faker.fake::<EndExportSessionResponse>()));
     mock_idesscd.expect_echo().returning(|request| Ok(ScuDeEchoResponse { message: request.message.clone() }));
+    mock_idesscd.expect_get_version().returning(|| Ok(Faker.fake::<ScuDeVersion>()));
 
     mock_idesscd
 });
@@ -65,11 +73,102 @@ impl Respond for FakerResponder {
     }
 }
 
+struct TlsServer {
+    url: String,
+    dir: String,
+    pin: String,
+}
+
+/// Number of requests the TLS test server received.
+static TLS_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Serves `GET /v1/version` over https with a certificate for `localhost` issued by a generated CA. The CA and a client certificate are written to `target/tls`.
+static SETUP_TLS_SERVER: Lazy<TlsServer> = Lazy::new(|| {
+    let dir = String::from("./target/tls");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut ca_params = rcgen::CertificateParams::new(vec![]);
+    ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    ca_params.distinguished_name.push(rcgen::DnType::CommonName, "middleware-wrapper-atrust test ca");
+    let ca = rcgen::Certificate::from_params(ca_params).unwrap();
+
+    let mut server_params = rcgen::CertificateParams::new(vec![String::from("localhost")]);
+    server_params.distinguished_name.push(rcgen::DnType::CommonName, "localhost");
+    let server = rcgen::Certificate::from_params(server_params).unwrap();
+    let server_der = server.serialize_der_with_signer(&ca).unwrap();
+    let server_pem = format!(
+        "-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----\n",
+        base64::encode(&server_der).as_bytes().chunks(64).map(|line| std::str::from_utf8(line).unwrap()).collect::<Vec<&str>>().join("\n")
+    );
+
+    let mut client_params = rcgen::CertificateParams::new(vec![]);
+    client_params.distinguished_name.push(rcgen::DnType::CommonName, "middleware-wrapper-atrust test client");
+    let client = rcgen::Certificate::from_params(client_params).unwrap();
+
+    std::fs::write(format!("{}/ca.pem", dir), ca.serialize_pem().unwrap()).unwrap();
+    std::fs::write(format!("{}/client.pem", dir), client.serialize_pem_with_signer(&ca).unwrap()).unwrap();
+    std::fs::write(format!("{}/client.key", dir), client.serialize_private_key_pem()).unwrap();
+
+    let identity = native_tls::Identity::from_pkcs8(server_pem.as_bytes(), server.serialize_private_key_pem().as_bytes()).unwrap();
+    let acceptor = Arc::new(native_tls::TlsAcceptor::new(identity).unwrap());
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let acceptor = acceptor.clone();
+
+            std::thread::spawn(move || {
+                let mut stream = match acceptor.accept(stream) {
+                    Ok(stream) => stream,
+                    Err(_) => return,
+                };
+
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => return,
+                        Ok(read) => request.extend_from_slice(&buf[..read]),
+                    }
+                }
+
+                TLS_REQUESTS.fetch_add(1, Ordering::SeqCst);
+
+                let body = serde_json::to_string(&ScuDeVersion { version: String::from("tls") }).unwrap();
+                let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+            });
+        }
+    });
+
+    TlsServer {
+        url: format!("https://localhost:{}", port),
+        dir,
+        pin: sha256::digest(server_der.as_slice()),
+    }
+});
+
+/// A `TseInfo` in the given state with a valid serial number and a certificate expiring at the start of `not_after_year`.
+fn fixed_tse_info(current_state: TseStates, not_after_year: i32) -> TseInfo {
+    let mut params = rcgen::CertificateParams::new(vec![]);
+    params.not_after = rcgen::date_time_ymd(not_after_year, 1, 1);
+    let certificate = rcgen::Certificate::from_params(params).unwrap();
+
+    TseInfo {
+        current_state,
+        serial_number_octet: hex::encode(b"fixed serial number"),
+        certificates_base64: vec![Base64::from(certificate.serialize_der().unwrap())],
+        ..Faker.fake()
+    }
+}
+
 static SETUP_MOCK_SERVER: Lazy<MockServer> = Lazy::new(|| {
     async_std::task::block_on(async {
         let mock_server = MockServer::start().await;
 
-        let config = std::fs::read_to_string(CONFIG_FILE).unwrap();
+        let tls_server = &*SETUP_TLS_SERVER;
+        let config = std::fs::read_to_string(CONFIG_FILE).unwrap().replace("{{ tls_url }}", &tls_server.url).replace("{{ tls_dir }}", &tls_server.dir).replace("{{ tls_pin }}", &tls_server.pin);
 
         if let Some(scu_url) = SCU_URL.as_ref() {
             std::fs::write(CONFIG_FILE_TARGET, config.replace("{{ scu_url }}", scu_url)).unwrap();
@@ -116,6 +215,75 @@ static SETUP_MOCK_SERVER: Lazy<MockServer> = Lazy::new(|| {
 
         Mock::given(method("POST")).and(path("/v1/echo")).respond_with(FakerResponder::post(|req| MOCK_IDESSCD.echo(&req).unwrap())).mount(&mock_server).await;
 
+        Mock::given(method("GET")).and(path("/v1/version")).respond_with(FakerResponder::get(|| MOCK_IDESSCD.get_version().unwrap())).mount(&mock_server).await;
+
+        Mock::given(method("GET"))
+            .and(path("/auth/api_key/v1/version"))
+            .and(header("X-Api-Key", "api-key-s3cr3t"))
+            .and(header("X-Tenant", "fiskaltrust"))
+            .respond_with(FakerResponder::get(|| MOCK_IDESSCD.get_version().unwrap()))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/auth/basic/v1/version"))
+            .and(header("Authorization", format!("Basic {}", base64::encode("vtss-id:password-s3cr3t")).as_str()))
+            .respond_with(FakerResponder::get(|| MOCK_IDESSCD.get_version().unwrap()))
+            .mount(&mock_server)
+            .await;
+
+        // slow never answers in time, garbage answers with an invalid body and fast is their fallback
+        Mock::given(method("POST")).and(path("/slow/v1/executesettsetime")).respond_with(ResponseTemplate::new(200).set_delay(std::time::Duration::from_secs(2))).mount(&mock_server).await;
+
+        Mock::given(method("GET")).and(path("/slow/v1/version")).respond_with(ResponseTemplate::new(200).set_delay(std::time::Duration::from_secs(2))).mount(&mock_server).await;
+
+        Mock::given(method("GET")).and(path("/garbage/v1/version")).respond_with(ResponseTemplate::new(200).set_body_string("not json")).mount(&mock_server).await;
+
+        Mock::given(method("POST")).and(path("/fast/v1/executesettsetime")).respond_with(ResponseTemplate::new(200)).mount(&mock_server).await;
+
+        Mock::given(method("GET")).and(path("/fast/v1/version")).respond_with(FakerResponder::get(|| MOCK_IDESSCD.get_version().unwrap())).mount(&mock_server).await;
+
+        // flaky fails three times and then answers slowly
+        Mock::given(method("GET")).and(path("/flaky/v1/version")).respond_with(ResponseTemplate::new(503)).up_to_n_times(3).mount(&mock_server).await;
+
+        Mock::given(method("GET"))
+            .and(path("/flaky/v1/version"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ScuDeVersion { version: String::from("flaky") }).set_delay(std::time::Duration::from_secs(1)))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("POST")).and(path("/flaky/v1/echo")).respond_with(FakerResponder::post(|req| MOCK_IDESSCD.echo(&req).unwrap())).mount(&mock_server).await;
+
+        // like the scu, answer GET requests to endpoints only accepting POST with 405 so they can be probed
+        Mock::given(method("GET"))
+            .and(path_regex(
+                r"^/v1/(starttransaction|updatetransaction|finishtransaction|tsestate|registerclientid|unregisterclientid|executesettsetime|executeselftest|startexportsession|startexportsessionbytimestamp|startexportsessionbytransaction|exportdata|endexportsession|echo)$",
+            ))
+            .respond_with(ResponseTemplate::new(405))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("POST")).and(path("/heartbeat")).respond_with(ResponseTemplate::new(204)).mount(&mock_server).await;
+
+        // tses with a fixed state for the checks which depend on it, the time of time_not_set can not be set
+        Mock::given(method("POST")).and(path("/time_not_set/v1/executesettsetime")).respond_with(ResponseTemplate::new(500)).mount(&mock_server).await;
+
+        for (prefix, tse_info) in [
+            ("initialized", fixed_tse_info(TseStates::Initialized, 4096)),
+            ("uninitialized", fixed_tse_info(TseStates::Uninitialized, 4096)),
+            ("terminated", fixed_tse_info(TseStates::Terminated, 4096)),
+            ("expired", fixed_tse_info(TseStates::Initialized, 2000)),
+            ("time_not_set", fixed_tse_info(TseStates::Initialized, 4096)),
+        ] {
+            Mock::given(method("GET")).and(path(format!("/{}/v1/tseinfo", prefix))).respond_with(ResponseTemplate::new(200).set_body_json(tse_info)).mount(&mock_server).await;
+
+            Mock::given(method("POST")).and(path(format!("/{}/v1/echo", prefix))).respond_with(FakerResponder::post(|req| MOCK_IDESSCD.echo(&req).unwrap())).mount(&mock_server).await;
+
+            Mock::given(method("GET")).and(path(format!("/{}/v1/version", prefix))).respond_with(FakerResponder::get(|| MOCK_IDESSCD.get_version().unwrap())).mount(&mock_server).await;
+
+            Mock::given(method("POST")).and(path(format!("/{}/v1/executesettsetime", prefix))).respond_with(ResponseTemplate::new(200)).mount(&mock_server).await;
+        }
+
         mock_server
     })
 });
@@ -273,3 +441,1247 @@ fn export_data_with_client_id() {
 
     unsafe { ffi::free_ptr(exported_data.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
 }
+
+#[test]
+#[serial]
+fn at_get_error_string() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_get_error_string_with_language = unsafe { dylib.symbol::<extern "C" fn(i32, u32, *mut *mut i8, *mut u32) -> i32>("at_getErrorStringWithLanguage").unwrap() };
+
+    let mut error_string = std::mem::MaybeUninit::<*mut i8>::uninit();
+    let mut error_string_length = std::mem::MaybeUninit::<u32>::uninit();
+
+    let result: ReturnCode = ReturnCode::try_from(at_get_error_string_with_language(ReturnCode::TseTimeout.into(), 1, error_string.as_mut_ptr(), error_string_length.as_mut_ptr())).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+    assert_eq!(unsafe { ffi::from_cstr(*error_string.as_ptr(), *error_string_length.as_ptr()) }, "ERROR_TSE_TIMEOUT (-3003): Die TSE hat nicht rechtzeitig geantwortet.");
+
+    unsafe { ffi::free_ptr(error_string.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+
+    let result: ReturnCode = ReturnCode::try_from(at_get_error_string_with_language(1, 0, error_string.as_mut_ptr(), error_string_length.as_mut_ptr())).unwrap();
+
+    assert_eq!(result, ReturnCode::ParameterMismatch);
+}
+
+#[test]
+#[serial]
+fn at_verify_config_entry_with_tse() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_verify_config_entry_with_tse = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("at_verifyConfigEntryWithTse").unwrap() };
+    let cfg_set_config_file = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("cfgSetConfigFile").unwrap() };
+    let verify = |tse_id: &str| ReturnCode::try_from(at_verify_config_entry_with_tse(tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+    assert_eq!(ReturnCode::ConfigValueNotFound, verify("unknown"));
+    assert_eq!(ReturnCode::ExecutionOk, verify("initialized"));
+    assert_eq!(ReturnCode::SeApiNotInitialized, verify("uninitialized"));
+    assert_eq!(ReturnCode::SecureElementDisabled, verify("terminated"));
+    assert_eq!(ReturnCode::SeCommunicationFailed, verify("unreachable"));
+
+    let broken_config = "./target/broken.conf";
+    let broken_entries = "\n[missing_url]\ntss_type = 2\n\n[bad_tss_type]\ntss_type = 9\nscu_url = http://127.0.0.1:1\n\n[bad_timeout]\ntss_type = 2\nscu_url = http://127.0.0.1:1\ntimeout = soon\n";
+    std::fs::write(broken_config, format!("{}{}", std::fs::read_to_string(CONFIG_FILE_TARGET).unwrap(), broken_entries)).unwrap();
+
+    assert_eq!(0, cfg_set_config_file(broken_config.as_ptr() as *const i8, broken_config.len() as u32));
+
+    let results = ["missing_url", "bad_tss_type", "bad_timeout"].map(verify);
+
+    assert_eq!(0, cfg_set_config_file(CONFIG_FILE_TARGET.as_ptr() as *const i8, CONFIG_FILE_TARGET.len() as u32));
+    assert_eq!([ReturnCode::InvalidConfig; 3], results);
+
+    let logs = recent_logs();
+
+    for problem in ["[missing_url] scu_url: missing, the entry is ignored", "[bad_tss_type] tss_type: unknown value 9, expected one of 1, 2", "[bad_timeout] timeout: expected a number"] {
+        assert!(logs.contains(&format!("[failed] config entry: {}", problem)), "{} not logged", problem);
+    }
+}
+
+#[test]
+#[serial]
+fn at_check_secure_state() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_check_secure_state_with_tse = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("at_checkSecureStateWithTse").unwrap() };
+    let update_time_with_time_sync_with_tse = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("updateTimeWithTimeSyncWithTse").unwrap() };
+    let check = |tse_id: &str| ReturnCode::try_from(at_check_secure_state_with_tse(tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+    assert_eq!(ReturnCode::SeInSecureState, check("initialized"));
+    assert_eq!(ReturnCode::SeApiNotInitialized, check("uninitialized"));
+    assert_eq!(ReturnCode::SecureElementDisabled, check("terminated"));
+    assert_eq!(ReturnCode::CertificateExpired, check("expired"));
+    assert_eq!(ReturnCode::SeCommunicationFailed, check("unreachable"));
+
+    let tse_id = "time_not_set";
+    assert_eq!(ReturnCode::SeInSecureState, check(tse_id));
+    assert_ne!(ReturnCode::ExecutionOk, ReturnCode::try_from(update_time_with_time_sync_with_tse(tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap());
+    assert_eq!(ReturnCode::TimeNotSet, check(tse_id));
+
+    let tse_id = "initialized";
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(update_time_with_time_sync_with_tse(tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap());
+    assert_eq!(ReturnCode::SeInSecureState, check(tse_id));
+}
+
+#[test]
+#[serial]
+fn at_reload_secure_element_with_tse() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_reload_secure_element_with_tse = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("at_reloadSecureElementWithTse").unwrap() };
+
+    let tse_id = "test";
+    let result: ReturnCode = ReturnCode::try_from(at_reload_secure_element_with_tse(tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+
+    let tse_id = "unknown";
+    let result: ReturnCode = ReturnCode::try_from(at_reload_secure_element_with_tse(tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+    assert_eq!(result, ReturnCode::ConfigValueNotFound);
+}
+
+#[test]
+#[serial]
+fn at_get_service_version() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_get_service_version = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32) -> i32>("at_getServiceVersion").unwrap() };
+
+    let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+    let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+    let result: ReturnCode = ReturnCode::try_from(at_get_service_version(version.as_mut_ptr(), version_length.as_mut_ptr())).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+
+    println!("version: {}", unsafe { ffi::from_cstr(*version.as_ptr(), *version_length.as_ptr()) });
+
+    unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+}
+
+#[test]
+#[serial]
+fn at_get_version_details() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_get_version_details = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32) -> i32>("at_getVersionDetails").unwrap() };
+
+    let mut version_details = std::mem::MaybeUninit::<*mut i8>::uninit();
+    let mut version_details_length = std::mem::MaybeUninit::<u32>::uninit();
+
+    let result: ReturnCode = ReturnCode::try_from(at_get_version_details(version_details.as_mut_ptr(), version_details_length.as_mut_ptr())).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+
+    let parsed: serde_json::Value = serde_json::from_str(&unsafe { ffi::from_cstr(*version_details.as_ptr(), *version_details_length.as_ptr()) }).unwrap();
+
+    assert_eq!(parsed["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(
+        parsed["tses"].as_array().unwrap().iter().map(|tse| tse["name"].as_str().unwrap()).collect::<Vec<&str>>(),
+        vec![
+            "auth_api_key",
+            "auth_basic",
+            "auth_missing",
+            "default",
+            "expired",
+            "failover",
+            "garbage",
+            "initialized",
+            "proxied",
+            "slow",
+            "terminated",
+            "test",
+            "time_not_set",
+            "tls",
+            "tls_insecure",
+            "tls_untrusted",
+            "tls_wrong_pin",
+            "uninitialized",
+            "unreachable"
+        ]
+    );
+    assert!(parsed["tses"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|tse| tse["scu_version"].is_string() != ["auth_missing", "garbage", "unreachable", "tls_untrusted", "tls_wrong_pin"].contains(&tse["name"].as_str().unwrap())));
+
+    unsafe { ffi::free_ptr(version_details.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+}
+
+#[test]
+#[serial]
+fn at_set_pins_with_tse() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_set_pins_with_tse = unsafe { dylib.symbol::<extern "C" fn(*const u8, u32, *const u8, u32, *const i8, u32) -> i32>("at_setPinsWithTse").unwrap() };
+    let authenticate_user_with_tse = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32, *const u8, u32, *mut i32, *mut i16, *const i8, u32) -> i32>("authenticateUserWithTse").unwrap() };
+    let unblock_user_with_tse = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32, *const i8, u32, *const i8, u32, *mut u32, *const i8, u32) -> i32>("unblockUserWithTse").unwrap() };
+
+    let tse_id = "test";
+    let set_pins = |pin: &str, puk: &str| ReturnCode::try_from(at_set_pins_with_tse(pin.as_ptr(), pin.len() as u32, puk.as_ptr(), puk.len() as u32, tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+    assert_eq!(set_pins("1234", "123456"), ReturnCode::AtSetPinsFailed);
+    assert_eq!(set_pins("12345", "123456"), ReturnCode::ExecutionOk);
+    assert_eq!(set_pins("12345", "123456"), ReturnCode::ExecutionOk);
+    assert_eq!(set_pins("54321", "654321"), ReturnCode::SeAlreadyProvisioned);
+
+    let authenticate = |user_id: &str, pin: &[u8], tse_id: &str| {
+        let (mut authentication_result, mut remaining_retries) = (-1, -1);
+        let result = ReturnCode::try_from(authenticate_user_with_tse(
+            user_id.as_ptr() as *const i8,
+            user_id.len() as u32,
+            pin.as_ptr(),
+            pin.len() as u32,
+            &mut authentication_result,
+            &mut remaining_retries,
+            tse_id.as_ptr() as *const i8,
+            tse_id.len() as u32,
+        ))
+        .unwrap();
+        (result, authentication_result, remaining_retries)
+    };
+
+    let unblock = |puk: &str, new_pin: &str| {
+        let mut unblock_result = u32::MAX;
+        let result = ReturnCode::try_from(unblock_user_with_tse(
+            "Admin".as_ptr() as *const i8,
+            "Admin".len() as u32,
+            puk.as_ptr() as *const i8,
+            puk.len() as u32,
+            new_pin.as_ptr() as *const i8,
+            new_pin.len() as u32,
+            &mut unblock_result,
+            tse_id.as_ptr() as *const i8,
+            tse_id.len() as u32,
+        ))
+        .unwrap();
+        (result, unblock_result)
+    };
+
+    assert_eq!(authenticate("Admin", b"12345", tse_id), (ReturnCode::ExecutionOk, 0, 3));
+    assert_eq!(authenticate("Admin", b"54321", tse_id), (ReturnCode::ExecutionOk, 1, 2));
+    assert_eq!(authenticate("Admin", b"12345", tse_id), (ReturnCode::ExecutionOk, 0, 3));
+    assert_eq!(authenticate("Admin", b"", tse_id), (ReturnCode::MissingParameter, 1, 0));
+    assert_eq!(authenticate("Cashier", b"12345", tse_id), (ReturnCode::ExecutionOk, 3, 0));
+    assert_eq!(authenticate("Admin", b"12345", "initialized"), (ReturnCode::ExecutionOk, 1, 0));
+
+    for remaining_retries in (0..3).rev() {
+        assert_eq!(authenticate("Admin", b"54321", tse_id), (ReturnCode::ExecutionOk, 1, remaining_retries));
+    }
+    assert_eq!(authenticate("Admin", b"12345", tse_id), (ReturnCode::ExecutionOk, 2, 0));
+
+    assert_eq!(unblock("654321", "67890"), (ReturnCode::ExecutionOk, 1));
+    assert_eq!(unblock("123456", "6789"), (ReturnCode::ParameterMismatch, 3));
+    assert_eq!(unblock("123456", "67890"), (ReturnCode::ExecutionOk, 0));
+    assert_eq!(authenticate("Admin", b"67890", tse_id), (ReturnCode::ExecutionOk, 0, 3));
+
+    // pins which are not valid UTF-8 are compared byte by byte
+    let (pin, other_pin, puk, binary_tse_id) = ([0xff_u8; 5], [0xfe_u8; 5], [0xff_u8; 6], "expired");
+    assert_eq!(
+        ReturnCode::ExecutionOk,
+        ReturnCode::try_from(at_set_pins_with_tse(pin.as_ptr(), pin.len() as u32, puk.as_ptr(), puk.len() as u32, binary_tse_id.as_ptr() as *const i8, binary_tse_id.len() as u32)).unwrap()
+    );
+    assert_eq!(authenticate("Admin", &other_pin, binary_tse_id), (ReturnCode::ExecutionOk, 1, 2));
+    assert_eq!(authenticate("Admin", &pin, binary_tse_id), (ReturnCode::ExecutionOk, 0, 3));
+}
+
+#[test]
+#[serial]
+fn at_set_pace_and_add_user_entropy() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_set_pace = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32, *const i8, u32, *const i8, u32) -> i32>("at_setPace").unwrap() };
+    let at_add_user_entropy = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("at_addUserEntropy").unwrap() };
+
+    let (user, pin, api_key) = ("user", "123456", "");
+    let result: ReturnCode = ReturnCode::try_from(at_set_pace(user.as_ptr() as *const i8, user.len() as u32, pin.as_ptr() as *const i8, pin.len() as u32, api_key.as_ptr() as *const i8, api_key.len() as u32)).unwrap();
+
+    assert_eq!(result, ReturnCode::MissingParameter);
+
+    let api_key = "api-key";
+    let result: ReturnCode = ReturnCode::try_from(at_set_pace(user.as_ptr() as *const i8, user.len() as u32, pin.as_ptr() as *const i8, pin.len() as u32, api_key.as_ptr() as *const i8, api_key.len() as u32)).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+
+    let entropy = "entropy";
+    let result: ReturnCode = ReturnCode::try_from(at_add_user_entropy(entropy.as_ptr() as *const i8, entropy.len() as u32)).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+}
+
+#[test]
+#[serial]
+fn at_check_compatibility() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_check_compatibility = unsafe { dylib.symbol::<extern "C" fn(u32, u32) -> i32>("at_checkCompatibility").unwrap() };
+
+    let result: ReturnCode = ReturnCode::try_from(at_check_compatibility(0, u32::MAX)).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+
+    let result: ReturnCode = ReturnCode::try_from(at_check_compatibility(1000, 1)).unwrap();
+
+    assert_eq!(result, ReturnCode::ParameterMismatch);
+
+    // the scu of initialized only serves tseinfo, echo, version and executesettsetime
+    let at_check_compatibility_with_tse = unsafe { dylib.symbol::<extern "C" fn(u32, u32, *const i8, u32) -> i32>("at_checkCompatibilityWithTse").unwrap() };
+    let check_compatibility = |start_index: u32, index_count: u32| ReturnCode::try_from(at_check_compatibility_with_tse(start_index, index_count, "initialized".as_ptr() as *const i8, "initialized".len() as u32)).unwrap();
+
+    assert_eq!(ReturnCode::ExecutionOk, check_compatibility(9, 6));
+    assert_eq!(ReturnCode::FunctionNotSupported, check_compatibility(4, 1));
+}
+
+#[test]
+#[serial]
+fn tse_info_cache() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_get_public_key_with_tse = unsafe { dylib.symbol::<extern "C" fn(*mut *mut u8, *mut u32, *const i8, u32) -> i32>("at_getPublicKeyWithTse").unwrap() };
+    let at_run_self_tests = unsafe { dylib.symbol::<extern "C" fn() -> i32>("at_runSelfTests").unwrap() };
+
+    let get_public_key = || {
+        let mut pub_key = std::mem::MaybeUninit::<*mut u8>::uninit();
+        let mut pub_key_length = std::mem::MaybeUninit::<u32>::uninit();
+
+        let tse_id = "default";
+        let result: ReturnCode = ReturnCode::try_from(at_get_public_key_with_tse(pub_key.as_mut_ptr(), pub_key_length.as_mut_ptr(), tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+        assert_eq!(result, ReturnCode::ExecutionOk);
+
+        let pub_key_string = unsafe { ffi::from_cstr(*pub_key.as_ptr() as *const i8, *pub_key_length.as_ptr()) };
+        unsafe { ffi::free_ptr(pub_key.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+
+        pub_key_string
+    };
+
+    let first = get_public_key();
+    assert_eq!(get_public_key(), first);
+
+    let result: ReturnCode = ReturnCode::try_from(at_run_self_tests()).unwrap();
+    assert_eq!(result, ReturnCode::ExecutionOk);
+
+    assert_ne!(get_public_key(), first);
+}
+
+#[test]
+#[serial]
+fn scu_failover() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_get_public_key_with_tse = unsafe { dylib.symbol::<extern "C" fn(*mut *mut u8, *mut u32, *const i8, u32) -> i32>("at_getPublicKeyWithTse").unwrap() };
+
+    let mut pub_key = std::mem::MaybeUninit::<*mut u8>::uninit();
+    let mut pub_key_length = std::mem::MaybeUninit::<u32>::uninit();
+
+    let tse_id = "failover";
+    let result: ReturnCode = ReturnCode::try_from(at_get_public_key_with_tse(pub_key.as_mut_ptr(), pub_key_length.as_mut_ptr(), tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+
+    unsafe { ffi::free_ptr(pub_key.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+}
+
+#[test]
+#[serial]
+fn failover_only_without_side_effects() {
+    let mock_server = Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let update_time_with_time_sync_with_tse = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("updateTimeWithTimeSyncWithTse").unwrap() };
+    let at_get_service_version_with_tse = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32, *const i8, u32) -> i32>("at_getServiceVersionWithTse").unwrap() };
+
+    let get_service_version = |tse_id: &str| {
+        let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+        let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+        let result = ReturnCode::try_from(at_get_service_version_with_tse(version.as_mut_ptr(), version_length.as_mut_ptr(), tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+        if result == ReturnCode::ExecutionOk {
+            unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+        }
+
+        result
+    };
+
+    let received = |url_path: &str| async_std::task::block_on(mock_server.received_requests()).unwrap().iter().filter(|request| request.url.path() == url_path).count();
+
+    // the timed out POST might have been processed by slow, so it must not be sent to fast
+    let tse_id = "slow";
+    assert_eq!(ReturnCode::SeCommunicationFailed, ReturnCode::try_from(update_time_with_time_sync_with_tse(tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap());
+    assert_eq!(0, received("/fast/v1/executesettsetime"));
+
+    // a timed out GET has no side effects and is sent to fast
+    let versions = received("/fast/v1/version");
+    assert_eq!(ReturnCode::ExecutionOk, get_service_version("slow"));
+    assert_eq!(versions + 1, received("/fast/v1/version"));
+
+    // an invalid response is no reason to ask fast
+    assert_eq!(ReturnCode::TseResponseDataInvalid, get_service_version("garbage"));
+    assert_eq!(versions + 1, received("/fast/v1/version"));
+}
+
+#[test]
+#[serial]
+fn circuit_breaker() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_get_public_key_with_tse = unsafe { dylib.symbol::<extern "C" fn(*mut *mut u8, *mut u32, *const i8, u32) -> i32>("at_getPublicKeyWithTse").unwrap() };
+    let at_get_circuit_breaker_state_with_tse = unsafe { dylib.symbol::<extern "C" fn(*mut u32, *const i8, u32) -> i32>("at_getCircuitBreakerStateWithTse").unwrap() };
+
+    let tse_id = "unreachable";
+
+    for _ in 0..3 {
+        let mut pub_key = std::mem::MaybeUninit::<*mut u8>::uninit();
+        let mut pub_key_length = std::mem::MaybeUninit::<u32>::uninit();
+
+        let result: ReturnCode = ReturnCode::try_from(at_get_public_key_with_tse(pub_key.as_mut_ptr(), pub_key_length.as_mut_ptr(), tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+        assert_eq!(result, ReturnCode::SeCommunicationFailed);
+    }
+
+    let mut state = 0u32;
+    let result: ReturnCode = ReturnCode::try_from(at_get_circuit_breaker_state_with_tse(&mut state, tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+    assert_eq!(state, 1);
+
+    // a changed config closes all circuit breakers
+    let cfg_set_config_file = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("cfgSetConfigFile").unwrap() };
+    assert_eq!(0, cfg_set_config_file(CONFIG_FILE_TARGET.as_ptr() as *const i8, CONFIG_FILE_TARGET.len() as u32));
+
+    let result: ReturnCode = ReturnCode::try_from(at_get_circuit_breaker_state_with_tse(&mut state, tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+    assert_eq!(state, 0);
+
+    let tse_id = "default";
+    let result: ReturnCode = ReturnCode::try_from(at_get_circuit_breaker_state_with_tse(&mut state, tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+    assert_eq!(state, 0);
+}
+
+#[test]
+#[serial]
+fn circuit_breaker_half_open() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let cfg_set_config_file = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("cfgSetConfigFile").unwrap() };
+    let at_get_service_version_with_tse: extern "C" fn(*mut *mut i8, *mut u32, *const i8, u32) -> i32 = unsafe { *dylib.symbol("at_getServiceVersionWithTse").unwrap() };
+    let at_get_circuit_breaker_state_with_tse = unsafe { dylib.symbol::<extern "C" fn(*mut u32, *const i8, u32) -> i32>("at_getCircuitBreakerStateWithTse").unwrap() };
+
+    let tse_id = "flaky";
+
+    let get_service_version = move || {
+        let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+        let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+        let result = ReturnCode::try_from(at_get_service_version_with_tse(version.as_mut_ptr(), version_length.as_mut_ptr(), tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+        if result == ReturnCode::ExecutionOk {
+            unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+        }
+
+        result
+    };
+
+    let state = || {
+        let mut state = u32::MAX;
+        assert_eq!(0, at_get_circuit_breaker_state_with_tse(&mut state, tse_id.as_ptr() as *const i8, tse_id.len() as u32));
+        state
+    };
+
+    let config = std::fs::read_to_string(CONFIG_FILE_TARGET).unwrap().replacen("[config]\n", "[config]\ncircuit_breaker_reset = 1\n", 1);
+    let scu_url = config.lines().find_map(|line| line.strip_prefix("scu_url = http://127.0.0.1")).map(|port| format!("http://127.0.0.1{}", port)).unwrap();
+    let flaky_config = "./target/flaky.conf";
+    std::fs::write(flaky_config, format!("{}\n[flaky]\ntss_type = 2\nscu_url = {}/flaky\n", config, scu_url)).unwrap();
+
+    assert_eq!(0, cfg_set_config_file(flaky_config.as_ptr() as *const i8, flaky_config.len() as u32));
+
+    // server errors count as failures
+    for _ in 0..3 {
+        assert_eq!(ReturnCode::Unknown, get_service_version());
+    }
+    assert_eq!(1, state());
+    assert_eq!(ReturnCode::SeCommunicationFailed, get_service_version());
+
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    assert_eq!(2, state());
+
+    // only the trial request reaches the scu, the others fail fast until it finished
+    let trial = std::thread::spawn(get_service_version);
+    std::thread::sleep(std::time::Duration::from_millis(300));
+
+    let started = std::time::Instant::now();
+    let rejected = get_service_version();
+    let rejected_after = started.elapsed();
+
+    let trial = trial.join().unwrap();
+    let closed = state();
+
+    assert_eq!(0, cfg_set_config_file(CONFIG_FILE_TARGET.as_ptr() as *const i8, CONFIG_FILE_TARGET.len() as u32));
+
+    assert_eq!(ReturnCode::SeCommunicationFailed, rejected);
+    assert!(rejected_after < std::time::Duration::from_millis(500), "{:?}", rejected_after);
+    assert_eq!(ReturnCode::ExecutionOk, trial);
+    assert_eq!(0, closed);
+}
+
+#[test]
+#[serial]
+fn tls_options() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_get_service_version_with_tse = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32, *const i8, u32) -> i32>("at_getServiceVersionWithTse").unwrap() };
+
+    for (tse_id, expected) in [
+        ("tls", ReturnCode::ExecutionOk),
+        ("tls_insecure", ReturnCode::ExecutionOk),
+        ("tls_untrusted", ReturnCode::SeCommunicationFailed),
+        ("tls_wrong_pin", ReturnCode::SeCommunicationFailed),
+    ] {
+        let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+        let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+        let result: ReturnCode = ReturnCode::try_from(at_get_service_version_with_tse(version.as_mut_ptr(), version_length.as_mut_ptr(), tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+        assert_eq!(result, expected, "{}", tse_id);
+
+        if result == ReturnCode::ExecutionOk {
+            assert_eq!(unsafe { ffi::from_cstr(*version.as_ptr(), *version_length.as_ptr()) }, "tls");
+            unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+        }
+    }
+}
+
+#[test]
+#[serial]
+fn tls_wrong_pin_sends_nothing() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_reload_secure_element_with_tse = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("at_reloadSecureElementWithTse").unwrap() };
+
+    let tse_id = "tls_wrong_pin";
+    let received = TLS_REQUESTS.load(Ordering::SeqCst);
+    let result: ReturnCode = ReturnCode::try_from(at_reload_secure_element_with_tse(tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+    assert_eq!(result, ReturnCode::SeCommunicationFailed);
+    assert_eq!(TLS_REQUESTS.load(Ordering::SeqCst), received);
+
+    let tse_id = "tls";
+    let received = TLS_REQUESTS.load(Ordering::SeqCst);
+    at_reload_secure_element_with_tse(tse_id.as_ptr() as *const i8, tse_id.len() as u32);
+
+    assert!(TLS_REQUESTS.load(Ordering::SeqCst) > received);
+}
+
+#[test]
+#[serial]
+fn per_tse_http_proxy() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_get_service_version_with_tse = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32, *const i8, u32) -> i32>("at_getServiceVersionWithTse").unwrap() };
+
+    let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+    let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+    let tse_id = "proxied";
+    let result: ReturnCode = ReturnCode::try_from(at_get_service_version_with_tse(version.as_mut_ptr(), version_length.as_mut_ptr(), tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+
+    unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+}
+
+#[test]
+#[serial]
+fn authenticated_requests() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_get_service_version_with_tse = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32, *const i8, u32) -> i32>("at_getServiceVersionWithTse").unwrap() };
+
+    for (tse_id, expected) in [("auth_api_key", ReturnCode::ExecutionOk), ("auth_basic", ReturnCode::ExecutionOk), ("auth_missing", ReturnCode::FunctionNotSupported)] {
+        let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+        let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+        let result: ReturnCode = ReturnCode::try_from(at_get_service_version_with_tse(version.as_mut_ptr(), version_length.as_mut_ptr(), tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+        assert_eq!(result, expected, "{}", tse_id);
+
+        if result == ReturnCode::ExecutionOk {
+            unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+        }
+    }
+}
+
+#[test]
+#[serial]
+fn correlation_id_header() {
+    let mock_server = Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_get_service_version = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32) -> i32>("at_getServiceVersion").unwrap() };
+
+    let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+    let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+    let result: ReturnCode = ReturnCode::try_from(at_get_service_version(version.as_mut_ptr(), version_length.as_mut_ptr())).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+
+    unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+
+    let requests = async_std::task::block_on(mock_server.received_requests()).unwrap();
+    let request = requests.iter().rev().find(|request| request.url.path() == "/v1/version").unwrap();
+    let correlation_id = request.headers.get(&"X-Correlation-Id".into()).unwrap();
+
+    assert!(uuid::Uuid::parse_str(correlation_id.as_str()).is_ok());
+}
+
+/// The content of all log files written to during the last minute.
+fn recent_logs() -> String {
+    std::fs::read_dir("./target/logs")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map(|extension| extension == "log").unwrap_or(false))
+        .filter(|path| std::fs::metadata(path).unwrap().modified().unwrap().elapsed().map(|elapsed| elapsed.as_secs() < 60).unwrap_or(true))
+        .map(|path| std::fs::read_to_string(path).unwrap())
+        .collect()
+}
+
+#[test]
+#[serial]
+fn secrets_are_redacted_from_logs() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let _ = &SETUP_ATRUSTAPI;
+
+    start_transaction();
+    authenticated_requests();
+    per_tse_http_proxy();
+
+    let log = recent_logs();
+
+    // log_bodies is enabled in the test config, so the process data is logged as sent
+    assert!(log.contains("request body: {\"ClientId\":"), "request body not logged");
+    assert!(log.contains(&format!("\"ProcessDataBase64\":\"{}\"", base64::encode("processData"))), "process data not logged");
+
+    for secret in ["api-key-s3cr3t", "password-s3cr3t", "time-admin-s3cr3t", "proxy-s3cr3t", &base64::encode("vtss-id:password-s3cr3t")] {
+        assert!(!log.contains(secret), "{} found in log", secret);
+    }
+}
+
+#[test]
+#[serial]
+fn log_rotation() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let cfg_set_log_rotate_size = unsafe { dylib.symbol::<extern "C" fn(u64) -> i32>("cfgSetLogRotateSize").unwrap() };
+    let cfg_set_log_rotate_age = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("cfgSetLogRotateAge").unwrap() };
+    let cfg_set_log_keep_files = unsafe { dylib.symbol::<extern "C" fn(u32) -> i32>("cfgSetLogKeepFiles").unwrap() };
+    let cfg_set_log_compress = unsafe { dylib.symbol::<extern "C" fn(bool) -> i32>("cfgSetLogCompress").unwrap() };
+    let at_get_service_version = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32) -> i32>("at_getServiceVersion").unwrap() };
+
+    assert_eq!(ReturnCode::InvalidConfig, ReturnCode::try_from(cfg_set_log_rotate_age("fortnight".as_ptr() as *const i8, "fortnight".len() as u32)).unwrap());
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_log_rotate_size(1024)).unwrap());
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_log_keep_files(2)).unwrap());
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_log_compress(true)).unwrap());
+
+    for _ in 0..20 {
+        let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+        let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+        assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(at_get_service_version(version.as_mut_ptr(), version_length.as_mut_ptr())).unwrap());
+
+        unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+    }
+
+    let log_files = || std::fs::read_dir("./target/logs").unwrap().map(|entry| entry.unwrap().path()).collect::<Vec<_>>();
+    let current = log_files().into_iter().filter(|path| path.to_string_lossy().ends_with("_rCURRENT.log")).max_by_key(|path| std::fs::metadata(path).unwrap().modified().unwrap()).unwrap();
+    let prefix = current.to_string_lossy().trim_end_matches("CURRENT.log").to_string();
+    let compressed = || log_files().into_iter().filter(|path| path.to_string_lossy().starts_with(&prefix) && path.to_string_lossy().ends_with(".gz")).count();
+
+    for _ in 0..50 {
+        if compressed() > 0 && compressed() <= 2 {
+            break;
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+
+    let compressed = compressed();
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_log_rotate_size(0)).unwrap());
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_log_keep_files(0)).unwrap());
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_log_compress(false)).unwrap());
+
+    assert!((1..=2).contains(&compressed), "{} compressed log files", compressed);
+}
+
+#[test]
+#[serial]
+fn json_log_format() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let cfg_set_log_format = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("cfgSetLogFormat").unwrap() };
+    let at_get_service_version_with_tse = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32, *const i8, u32) -> i32>("at_getServiceVersionWithTse").unwrap() };
+    let at_get_service_version = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32) -> i32>("at_getServiceVersion").unwrap() };
+
+    assert_eq!(ReturnCode::InvalidConfig, ReturnCode::try_from(cfg_set_log_format("xml".as_ptr() as *const i8, "xml".len() as u32)).unwrap());
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_log_format("json".as_ptr() as *const i8, "json".len() as u32)).unwrap());
+
+    let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+    let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+    let tse_id = "test";
+    let result: ReturnCode = ReturnCode::try_from(at_get_service_version_with_tse(version.as_mut_ptr(), version_length.as_mut_ptr(), tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+
+    unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(at_get_service_version(version.as_mut_ptr(), version_length.as_mut_ptr())).unwrap());
+
+    unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+
+    let log = recent_logs();
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_log_format("text".as_ptr() as *const i8, "text".len() as u32)).unwrap());
+
+    let lines: Vec<serde_json::Value> = log.lines().filter(|line| line.starts_with('{')).map(|line| serde_json::from_str(line).unwrap()).collect();
+    let returned = lines.iter().rev().find(|line| line["function"] == "at_getServiceVersionWithTse" && line["tse_id"] == tse_id && !line["return_code"].is_null()).unwrap();
+
+    assert_eq!(returned["return_code"], 0);
+    assert_eq!(returned["level"], "DEBUG");
+    assert!(returned["module"].is_string());
+    assert!(chrono::DateTime::parse_from_rfc3339(returned["timestamp"].as_str().unwrap()).is_ok());
+    assert!(uuid::Uuid::parse_str(returned["correlation_id"].as_str().unwrap()).is_ok());
+    assert!(lines
+        .iter()
+        .any(|line| line["function"] == "at_getServiceVersionWithTse" && line["message"] == "at_getServiceVersionWithTse" && line["correlation_id"] == returned["correlation_id"]));
+
+    let default = lines.iter().rev().find(|line| line["function"] == "at_getServiceVersionWithTse" && line["tse_id"] == "default" && !line["return_code"].is_null()).unwrap();
+
+    assert_eq!(default["return_code"], 0);
+    assert_ne!(default["correlation_id"], returned["correlation_id"]);
+    assert!(lines.iter().filter(|line| line["correlation_id"] == default["correlation_id"]).all(|line| line["function"] == "at_getServiceVersionWithTse"));
+    assert!(lines.iter().any(|line| line["message"] == "at_getServiceVersion"));
+}
+
+fn start_transaction_with_process_type(client_id: &str, process_type: &str) -> ReturnCode {
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let start_transaction = unsafe {
+        dylib
+            .symbol::<extern "C" fn(*const i8, u32, *const u8, u32, *const i8, u32, *const u8, u32, *mut u32, *mut i64, *mut *mut u8, *mut u32, *mut u32, *mut *mut u8, *mut u32) -> i32>("startTransaction")
+            .unwrap()
+    };
+
+    let mut transaction_number = std::mem::MaybeUninit::<u32>::uninit();
+    let mut log_time = std::mem::MaybeUninit::<i64>::uninit();
+    let mut serial_number = std::mem::MaybeUninit::<*mut u8>::uninit();
+    let mut serial_number_length = std::mem::MaybeUninit::<u32>::uninit();
+    let mut signature_counter = std::mem::MaybeUninit::<u32>::uninit();
+    let mut signature_value = std::mem::MaybeUninit::<*mut u8>::uninit();
+    let mut signature_value_length = std::mem::MaybeUninit::<u32>::uninit();
+
+    let result = ReturnCode::try_from(start_transaction(
+        client_id.as_ptr() as *const i8,
+        client_id.len() as u32,
+        "processData".as_bytes().as_ptr(),
+        "processData".len() as u32,
+        process_type.as_ptr() as *const i8,
+        process_type.len() as u32,
+        "additionalData".as_bytes().as_ptr(),
+        "additionalData".len() as u32,
+        transaction_number.as_mut_ptr(),
+        log_time.as_mut_ptr(),
+        serial_number.as_mut_ptr(),
+        serial_number_length.as_mut_ptr(),
+        signature_counter.as_mut_ptr(),
+        signature_value.as_mut_ptr(),
+        signature_value_length.as_mut_ptr(),
+    ))
+    .unwrap();
+
+    if result == ReturnCode::ExecutionOk {
+        unsafe { ffi::free_ptr(serial_number.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+        unsafe { ffi::free_ptr(signature_value.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+    }
+
+    result
+}
+
+#[test]
+#[serial]
+fn audit_log() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let cfg_set_max_audit_log_size = unsafe { dylib.symbol::<extern "C" fn(u32) -> i32>("cfgSetMaxAuditLogSize").unwrap() };
+
+    let audit_log = "./target/logs/audit/default.log";
+    let client_id = at_register_client_id_internal();
+
+    assert_eq!(start_transaction_with_process_type(&client_id, "processType"), ReturnCode::ExecutionOk);
+
+    let entry: serde_json::Value = serde_json::from_str(std::fs::read_to_string(audit_log).unwrap().lines().last().unwrap()).unwrap();
+
+    assert_eq!(entry["tse_id"], "default");
+    assert_eq!(entry["operation"], "start_transaction");
+    assert_eq!(entry["client_id"], client_id.as_str());
+    assert_eq!(entry["process_type"], "processType");
+    assert_eq!(entry["process_data_base64"], base64::encode("processData"));
+    assert_eq!(entry["process_data_length"], "processData".len());
+    assert_eq!(entry["process_data_sha256"], sha256::digest("processData"));
+    assert_eq!(entry["result"], "ok");
+    assert!(entry["transaction_number"].is_u64());
+    assert!(entry["signature_counter"].is_u64());
+    assert!(chrono::DateTime::parse_from_rfc3339(entry["timestamp"].as_str().unwrap()).is_ok());
+
+    let update_time_with_time_sync = unsafe { dylib.symbol::<extern "C" fn() -> i32>("updateTimeWithTimeSync").unwrap() };
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(update_time_with_time_sync()).unwrap());
+
+    let entry: serde_json::Value = serde_json::from_str(std::fs::read_to_string(audit_log).unwrap().lines().last().unwrap()).unwrap();
+
+    assert_eq!(entry["operation"], "execute_set_tse_time");
+    assert_eq!(entry["result"], "ok");
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_max_audit_log_size(1)).unwrap());
+
+    let process_type = "x".repeat(200 * 1024);
+
+    let dropped = recent_logs().matches("dropped the audit log history").count();
+
+    for _ in 0..6 {
+        assert_eq!(start_transaction_with_process_type(&client_id, &process_type), ReturnCode::ExecutionOk);
+    }
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_max_audit_log_size(128)).unwrap());
+
+    let audit_logs: Vec<_> = std::fs::read_dir("./target/logs/audit").unwrap().map(|entry| entry.unwrap().path()).filter(|path| path.file_name().unwrap().to_string_lossy().starts_with("default.")).collect();
+    let entries = audit_logs.iter().flat_map(|path| std::fs::read_to_string(path).unwrap().lines().map(String::from).collect::<Vec<_>>()).filter(|line| line.contains(&process_type)).count();
+
+    assert!(std::path::Path::new("./target/logs/audit/default.1.log").exists());
+    assert!(std::path::Path::new("./target/logs/audit/default.2.log").exists());
+    assert!(audit_logs.iter().map(|path| std::fs::metadata(path).unwrap().len()).sum::<u64>() <= 1024 * 1024);
+    assert!(entries >= 4, "only {} entries kept", entries);
+    assert!(recent_logs().matches("dropped the audit log history").count() > dropped);
+}
+
+#[test]
+#[serial]
+fn syslog_and_journald_targets() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let bind = |path: &str| {
+        let _ = std::fs::remove_file(path);
+        let socket = std::os::unix::net::UnixDatagram::bind(path).unwrap();
+        socket.set_read_timeout(Some(std::time::Duration::from_secs(2))).unwrap();
+        socket
+    };
+
+    let syslog = bind("./target/syslog.sock");
+    let journald = bind("./target/journald.sock");
+
+    let at_get_service_version = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32) -> i32>("at_getServiceVersion").unwrap() };
+
+    let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+    let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(at_get_service_version(version.as_mut_ptr(), version_length.as_mut_ptr())).unwrap());
+
+    unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+
+    let receive = |socket: &std::os::unix::net::UnixDatagram, needle: &str| loop {
+        let mut buffer = vec![0u8; 64 * 1024];
+        let length = socket.recv(&mut buffer).unwrap();
+        let datagram = String::from_utf8_lossy(&buffer[..length]).to_string();
+
+        if datagram.contains(needle) {
+            return datagram;
+        }
+    };
+
+    let line = receive(&syslog, "] at_getServiceVersion");
+    let priority = 19 * 8 + 6;
+
+    assert!(line.starts_with(&format!("<{}>", priority)), "{}", line);
+    assert!(line.contains(&format!(" asigntse-test[{}]: [", std::process::id())), "{}", line);
+
+    let entry = receive(&journald, "MESSAGE=at_getServiceVersionWithTse\n");
+
+    for field in ["PRIORITY=6\n", "SYSLOG_FACILITY=19\n", "SYSLOG_IDENTIFIER=asigntse-test\n", "FUNCTION=at_getServiceVersionWithTse\n", "CORRELATION_ID="] {
+        assert!(entry.contains(field), "{} missing in {}", field, entry);
+    }
+
+    drop((syslog, journald));
+    let _ = std::fs::remove_file("./target/syslog.sock");
+    let _ = std::fs::remove_file("./target/journald.sock");
+}
+
+#[test]
+#[serial]
+fn heartbeat() {
+    let mock_server = Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let cfg_set_upload_message_interval = unsafe { dylib.symbol::<extern "C" fn(u32) -> i32>("cfgSetUploadMessageInterval").unwrap() };
+
+    let heartbeats = || async_std::task::block_on(mock_server.received_requests()).unwrap().into_iter().filter(|request| request.url.path() == "/heartbeat").count();
+    let sent = heartbeats();
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_upload_message_interval(1)).unwrap());
+
+    for _ in 0..100 {
+        if heartbeats() > sent {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_upload_message_interval(86400)).unwrap());
+
+    let requests = async_std::task::block_on(mock_server.received_requests()).unwrap();
+    let request = requests.iter().rev().find(|request| request.url.path() == "/heartbeat").expect("no heartbeat was sent");
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    let tse = |tse_id: &str| body["tses"].as_array().unwrap().iter().find(|tse| tse["tse_id"] == tse_id).cloned().unwrap();
+
+    assert_eq!(tse("default")["reachable"], true);
+    assert!(tse("default")["max_log_memory_size"].is_number());
+    assert_eq!(tse("unreachable")["reachable"], false);
+    assert!(tse("unreachable")["error"].is_string());
+}
+
+#[test]
+#[serial]
+fn at_unload_and_reload() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let at_load = unsafe { dylib.symbol::<extern "C" fn() -> i32>("at_load").unwrap() };
+    let at_unload = unsafe { dylib.symbol::<extern "C" fn() -> i32>("at_unload").unwrap() };
+    let at_get_service_version = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32) -> i32>("at_getServiceVersion").unwrap() };
+
+    let get_service_version = || {
+        let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+        let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+        let result = ReturnCode::try_from(at_get_service_version(version.as_mut_ptr(), version_length.as_mut_ptr())).unwrap();
+
+        if result == ReturnCode::ExecutionOk {
+            unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+        }
+
+        result
+    };
+
+    let at_set_pins = unsafe { dylib.symbol::<extern "C" fn(*const u8, u32, *const u8, u32) -> i32>("at_setPins").unwrap() };
+    let at_set_pace = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32, *const i8, u32, *const i8, u32) -> i32>("at_setPace").unwrap() };
+    let at_add_user_entropy = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("at_addUserEntropy").unwrap() };
+    let at_get_circuit_breaker_state = unsafe { dylib.symbol::<extern "C" fn(*mut u32) -> i32>("at_getCircuitBreakerState").unwrap() };
+    let at_check_compatibility = unsafe { dylib.symbol::<extern "C" fn(u32, u32) -> i32>("at_checkCompatibility").unwrap() };
+    let at_verify_config_entry = unsafe { dylib.symbol::<extern "C" fn() -> i32>("at_verifyConfigEntry").unwrap() };
+
+    let (pin, puk, pace) = ("12345", "123456", "pace");
+    let mut state = std::mem::MaybeUninit::<u32>::uninit();
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(at_set_pins(pin.as_ptr(), pin.len() as u32, puk.as_ptr(), puk.len() as u32)).unwrap());
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(at_unload()).unwrap());
+    assert_eq!(ReturnCode::AtLoadNotCalled, get_service_version());
+
+    assert_eq!(ReturnCode::AtLoadNotCalled, ReturnCode::try_from(at_set_pins(pin.as_ptr(), pin.len() as u32, puk.as_ptr(), puk.len() as u32)).unwrap());
+    assert_eq!(
+        ReturnCode::AtLoadNotCalled,
+        ReturnCode::try_from(at_set_pace(pace.as_ptr() as *const i8, pace.len() as u32, pace.as_ptr() as *const i8, pace.len() as u32, pace.as_ptr() as *const i8, pace.len() as u32)).unwrap()
+    );
+    assert_eq!(ReturnCode::AtLoadNotCalled, ReturnCode::try_from(at_add_user_entropy(pace.as_ptr() as *const i8, pace.len() as u32)).unwrap());
+    assert_eq!(ReturnCode::AtLoadNotCalled, ReturnCode::try_from(at_get_circuit_breaker_state(state.as_mut_ptr())).unwrap());
+    assert_eq!(ReturnCode::AtLoadNotCalled, ReturnCode::try_from(at_check_compatibility(0, 1)).unwrap());
+    assert_eq!(ReturnCode::AtLoadNotCalled, ReturnCode::try_from(at_verify_config_entry()).unwrap());
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(at_load()).unwrap());
+    assert_eq!(ReturnCode::ExecutionOk, get_service_version());
+
+    let (new_pin, new_puk) = ("54321", "654321");
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(at_set_pins(new_pin.as_ptr(), new_pin.len() as u32, new_puk.as_ptr(), new_puk.len() as u32)).unwrap());
+}
+
+#[test]
+#[serial]
+fn config_discovery_and_env_overrides() {
+    let mock_server = Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let cfg_set_config_file = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("cfgSetConfigFile").unwrap() };
+    let at_get_service_version_with_tse = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32, *const i8, u32) -> i32>("at_getServiceVersionWithTse").unwrap() };
+
+    let discovered_config = "./target/discovered/asigntseonline.conf";
+    std::fs::create_dir_all("./target/discovered").unwrap();
+    std::fs::write(discovered_config, format!("{}\n[discovered]\ntss_type = 2\nscu_url = http://127.0.0.1:1\n", std::fs::read_to_string(CONFIG_FILE_TARGET).unwrap())).unwrap();
+
+    std::env::set_var("ASIGNTSE_CONFIG", discovered_config);
+    std::env::set_var("ASIGNTSE_DISCOVERED__SCU_URL", mock_server.uri());
+
+    let result = ReturnCode::try_from(cfg_set_config_file("".as_ptr() as *const i8, 0)).unwrap();
+
+    std::env::remove_var("ASIGNTSE_CONFIG");
+    std::env::remove_var("ASIGNTSE_DISCOVERED__SCU_URL");
+
+    assert_eq!(result, ReturnCode::ExecutionOk);
+
+    let tse_id = "discovered";
+    let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+    let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+    let result = ReturnCode::try_from(at_get_service_version_with_tse(version.as_mut_ptr(), version_length.as_mut_ptr(), tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_config_file(CONFIG_FILE_TARGET.as_ptr() as *const i8, CONFIG_FILE_TARGET.len() as u32)).unwrap());
+    assert_eq!(result, ReturnCode::ExecutionOk);
+
+    unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+}
+
+#[test]
+#[serial]
+fn strict_config_validation() {
+    let mock_server = Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let cfg_set_config_file = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("cfgSetConfigFile").unwrap() };
+    let at_get_service_version_with_tse = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32, *const i8, u32) -> i32>("at_getServiceVersionWithTse").unwrap() };
+    let set_config_file = |path: &str| ReturnCode::try_from(cfg_set_config_file(path.as_ptr() as *const i8, path.len() as u32)).unwrap();
+
+    let get_service_version = |tse_id: &str| {
+        let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+        let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+        let result = ReturnCode::try_from(at_get_service_version_with_tse(version.as_mut_ptr(), version_length.as_mut_ptr(), tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+        if result == ReturnCode::ExecutionOk {
+            unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+        }
+
+        result
+    };
+
+    let config = std::fs::read_to_string(CONFIG_FILE_TARGET).unwrap();
+    let problems = "\n[invalid]\ntss_type = 9\nscu_url = ftp://scu\ntimeout = soon\nunknown_key = 1\n\n[renamed]\nname = test\ntss_type = 2\nscu_url = http://127.0.0.1:1\n";
+
+    let lenient_config = "./target/lenient.conf";
+    std::fs::write(lenient_config, format!("{}{}", config, problems)).unwrap();
+
+    assert_eq!(ReturnCode::ExecutionOk, set_config_file(lenient_config));
+
+    let strict_config = "./target/strict.conf";
+    std::fs::write(strict_config, format!("{}{}\n[strict_only]\ntss_type = 2\nscu_url = {}\n", config.replacen("[config]\n", "[config]\nstrict_config = true\n", 1), problems, mock_server.uri())).unwrap();
+
+    let result = set_config_file(strict_config);
+    let strict_only = get_service_version("strict_only");
+    let previous = get_service_version("default");
+
+    assert_eq!(ReturnCode::ExecutionOk, set_config_file(CONFIG_FILE_TARGET));
+    assert_eq!(ReturnCode::InvalidConfig, result);
+    assert_ne!(ReturnCode::ExecutionOk, strict_only);
+    assert_eq!(ReturnCode::ExecutionOk, previous);
+
+    let logs = recent_logs();
+
+    for problem in [
+        "[invalid] tss_type: unknown value 9, expected one of 1, 2",
+        "[invalid] scu_url: unsupported url scheme ftp",
+        "[invalid] timeout: expected a number",
+        "[invalid] unknown_key: unknown key",
+        "[renamed] name: test is already used by [test]",
+    ] {
+        assert!(logs.contains(problem), "{} not logged", problem);
+    }
+}
+
+#[test]
+#[serial]
+fn config_hot_reload() {
+    let mock_server = Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let cfg_set_config_file = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("cfgSetConfigFile").unwrap() };
+    let at_get_service_version_with_tse = unsafe { dylib.symbol::<extern "C" fn(*mut *mut i8, *mut u32, *const i8, u32) -> i32>("at_getServiceVersionWithTse").unwrap() };
+
+    let at_reload_secure_element_with_tse = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("at_reloadSecureElementWithTse").unwrap() };
+
+    let get_service_version = |tse_id: &str| {
+        let mut version = std::mem::MaybeUninit::<*mut i8>::uninit();
+        let mut version_length = std::mem::MaybeUninit::<u32>::uninit();
+
+        let result = ReturnCode::try_from(at_get_service_version_with_tse(version.as_mut_ptr(), version_length.as_mut_ptr(), tse_id.as_ptr() as *const i8, tse_id.len() as u32)).unwrap();
+
+        if result == ReturnCode::ExecutionOk {
+            unsafe { ffi::free_ptr(version.as_mut_ptr() as *mut *mut std::os::raw::c_void) };
+        }
+
+        result
+    };
+
+    let wait_for_log = |needle: &str, seen: usize| {
+        for _ in 0..50 {
+            if recent_logs().matches(needle).count() > seen {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+
+        panic!("{} not logged", needle);
+    };
+
+    let config = std::fs::read_to_string(CONFIG_FILE_TARGET).unwrap().replacen("[config]\n", "[config]\nconfig_reload_interval = 1\n", 1);
+    let strict_config = config.replacen("[config]\n", "[config]\nstrict_config = true\n", 1);
+    let reloaded_config = "./target/reload.conf";
+    let write = |config: &str, scu_url: &str, extra: &str| std::fs::write(reloaded_config, format!("{}\n[reloaded]\ntss_type = 2\nscu_url = {}\n{}", config, scu_url, extra)).unwrap();
+
+    write(&config, "http://127.0.0.1:1", "");
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_config_file(reloaded_config.as_ptr() as *const i8, reloaded_config.len() as u32)).unwrap());
+
+    let changed = recent_logs().matches("config changed: [reloaded] scu_url changed").count();
+    write(&config, &mock_server.uri(), "");
+    wait_for_log("config changed: [reloaded] scu_url changed", changed);
+
+    assert_eq!(ReturnCode::ExecutionOk, get_service_version("reloaded"));
+
+    // reloading a single entry leaves the other changes of the file to the hot reload
+    let added = recent_logs().matches("config changed: [config] tse_info_cache_ttl added").count();
+    write(&config.replacen("[config]\n", "[config]\ntse_info_cache_ttl = 31\n", 1), &mock_server.uri(), "timeout = 1000\n");
+    let reloaded = ReturnCode::try_from(at_reload_secure_element_with_tse("reloaded".as_ptr() as *const i8, "reloaded".len() as u32)).unwrap();
+    wait_for_log("config changed: [config] tse_info_cache_ttl added", added);
+
+    assert_eq!(ReturnCode::ExecutionOk, reloaded);
+
+    let changed = recent_logs().matches("config changed: [reloaded] scu_url changed").count();
+    write(&config, "http://127.0.0.1:1", "unknown_key = 1\n");
+    wait_for_log("config changed: [reloaded] scu_url changed", changed);
+
+    let lenient = get_service_version("reloaded");
+
+    let changed = recent_logs().matches("config changed: [reloaded] scu_url changed").count();
+    write(&strict_config, &mock_server.uri(), "");
+    wait_for_log("config changed: [reloaded] scu_url changed", changed);
+
+    let kept = recent_logs().matches("keeping the previous config").count();
+    write(&strict_config, "http://127.0.0.1:1", "unknown_key = 1\n");
+    wait_for_log("keeping the previous config", kept);
+
+    let strict = get_service_version("reloaded");
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_config_file(CONFIG_FILE_TARGET.as_ptr() as *const i8, CONFIG_FILE_TARGET.len() as u32)).unwrap());
+    assert_eq!(ReturnCode::SeCommunicationFailed, lenient);
+    assert_eq!(ReturnCode::ExecutionOk, strict);
+}
+
+/// The sections of an INI file as JSON object, typed like deployment tooling would write them: booleans and numbers unquoted and `log_target` as list.
+fn ini_to_json(ini: &str) -> serde_json::Value {
+    let mut document = serde_json::Map::new();
+    let mut section = String::new();
+
+    for line in ini.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            section = name.to_string();
+            document.insert(section.clone(), serde_json::json!({}));
+            continue;
+        }
+
+        let (key, value) = line.split_once('=').unwrap();
+        let (key, value) = (key.trim(), value.trim());
+
+        let value = match (key, value.parse::<bool>(), value.parse::<u64>()) {
+            ("log_target", _, _) => value.split(',').map(str::trim).collect::<Vec<&str>>().into(),
+            (_, Ok(value), _) => value.into(),
+            (_, _, Ok(number)) if number.to_string() == value => number.into(),
+            _ => value.into(),
+        };
+
+        document[&section].as_object_mut().unwrap().insert(key.to_string(), value);
+    }
+
+    serde_json::Value::Object(document)
+}
+
+#[test]
+#[serial]
+fn config_formats_round_trip() {
+    Lazy::<MockServer>::force(&SETUP_MOCK_SERVER);
+    let dylib = &SETUP_ATRUSTAPI;
+
+    let cfg_set_config_file = unsafe { dylib.symbol::<extern "C" fn(*const i8, u32) -> i32>("cfgSetConfigFile").unwrap() };
+
+    let config = std::fs::read_to_string(CONFIG_FILE_TARGET).unwrap();
+    let document = ini_to_json(&config);
+
+    // the effective configs are told apart by the path they are logged with, which must not match those of earlier runs
+    let ini_config = &format!("./target/round_trip_{}.conf", std::process::id());
+    let toml_config = &format!("./target/round_trip_{}.toml", std::process::id());
+    let json_config = &format!("./target/round_trip_{}.json", std::process::id());
+
+    std::fs::write(ini_config, config).unwrap();
+    std::fs::write(toml_config, toml::to_string(&document).unwrap()).unwrap();
+    std::fs::write(json_config, serde_json::to_string_pretty(&document).unwrap()).unwrap();
+
+    for path in [ini_config, toml_config, json_config, CONFIG_FILE_TARGET] {
+        assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_config_file(path.as_ptr() as *const i8, path.len() as u32)).unwrap(), "{}", path);
+    }
+
+    let logs = recent_logs();
+    let effective = |path: &str| {
+        let needle = format!("config in effect from {}: ", path);
+        logs.lines().filter_map(|line| line.split_once(&needle).map(|(_, config)| config.to_string())).collect::<std::collections::BTreeSet<String>>()
+    };
+
+    let ini = effective(ini_config);
+
+    assert!(ini.iter().any(|config| config.starts_with("GeneralConfig {")));
+    assert!(ini.iter().any(|config| config.contains("name: \"default\"")));
+    assert_eq!(ini, effective(toml_config));
+    assert_eq!(ini, effective(json_config));
+
+    // unlike INI values TOML and JSON values have to have the type of their key
+    std::fs::write(
+        toml_config,
+        format!("{}\n[mistyped]\ntss_type = 2\nscu_url = \"http://127.0.0.1:1\"\ntimeout = \"5000\"\ntls_accept_invalid_certs = \"true\"\n", toml::to_string(&document).unwrap()),
+    )
+    .unwrap();
+
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_config_file(toml_config.as_ptr() as *const i8, toml_config.len() as u32)).unwrap());
+    assert_eq!(ReturnCode::ExecutionOk, ReturnCode::try_from(cfg_set_config_file(CONFIG_FILE_TARGET.as_ptr() as *const i8, CONFIG_FILE_TARGET.len() as u32)).unwrap());
+
+    let logs = recent_logs();
+
+    for problem in ["[mistyped] timeout: expected a number", "[mistyped] tls_accept_invalid_certs: expected true or false", "[mistyped] is ignored"] {
+        assert!(logs.contains(problem), "{} not logged", problem);
+    }
+
+    for path in [ini_config, toml_config, json_config] {
+        std::fs::remove_file(path).unwrap();
+    }
+}